@@ -0,0 +1,169 @@
+//! Sorts whose cost follows a measure of [presortedness](crate::presorted)
+//! rather than the length alone.
+
+use super::*;
+
+pub mod natural {
+    use super::*;
+    use crate::mergesort::merge;
+
+    /// Natural merge sort: finds the non-decreasing runs already in the
+    /// input and merges neighbouring runs pairwise until one is left. That
+    /// takes `n - 1` comparisons on sorted input and `O(n log r)` for `r`
+    /// runs. Stable.
+    pub struct Algo;
+
+    impl Sort for Algo {
+        const STABLE: bool = true;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            let mut vs = Tracked::new(vs, observer);
+            let len = vs.len();
+            // run `k` is `bounds[k]..bounds[k + 1]`
+            let mut bounds = vec![0];
+            for i in 1..len {
+                if vs.compare(i, i - 1, &mut compare) == Ordering::Less {
+                    bounds.push(i);
+                }
+            }
+            bounds.push(len);
+
+            let mut buf = Vec::new();
+            while bounds.len() > 2 {
+                let mut merged = Vec::with_capacity(bounds.len() / 2 + 1);
+                for pair in bounds.chunks(2) {
+                    merged.push(pair[0]);
+                }
+                for k in (0..bounds.len() - 2).step_by(2) {
+                    let (start, mid, end) = (bounds[k], bounds[k + 1], bounds[k + 2]);
+                    merge(
+                        &mut vs.slice(start..end),
+                        mid - start,
+                        &mut buf,
+                        &mut compare,
+                    );
+                }
+                // an odd run out stays as it is, its end is still `len`
+                if merged.last() != Some(&len) {
+                    merged.push(len);
+                }
+                bounds = merged;
+            }
+        }
+    }
+}
+
+pub mod splitsort {
+    use super::*;
+    use crate::{
+        keysort::permute,
+        select::{introselect, Select},
+    };
+
+    /// Levcopoulos and Petersson's split sort. One pass keeps an ascending
+    /// sequence on a stack, throwing out the top together with any element
+    /// that would descend from it; the thrown-out elements are split at
+    /// their median, sorted recursively and merged back in. That is
+    /// `O(n log Rem)` comparisons, `n - 1` on sorted input, but about
+    /// three times merge sort's on random input.
+    ///
+    /// It works on positions and moves the elements once at the end. Not
+    /// stable.
+    pub struct Algo;
+
+    impl Sort for Algo {
+        const STABLE: bool = false;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            let mut vs = Tracked::new(vs, observer);
+            let order = splitsort(&vs, (0..vs.len()).collect(), &mut compare);
+            permute(&mut vs, order);
+        }
+    }
+
+    /// Returns `idx` ordered by the elements at those positions.
+    fn splitsort<T, F, O>(vs: &Tracked<T, O>, idx: Vec<usize>, compare: &mut F) -> Vec<usize>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        // (ascending, thrown out)
+        let mut kept: Vec<usize> = Vec::with_capacity(idx.len());
+        let mut out = Vec::new();
+        for i in idx {
+            match kept.last() {
+                Some(&top) if vs.compare(i, top, compare) == Ordering::Less => {
+                    kept.pop();
+                    out.extend([top, i]);
+                }
+                _ => kept.push(i),
+            }
+        }
+        if out.is_empty() {
+            return kept;
+        }
+
+        // selecting the median leaves the rest split around it
+        let mid = out.len() / 2;
+        introselect::Algo.select_nth_by(&mut out, mid, |&a, &b| vs.compare(a, b, compare));
+        let large = out.split_off(mid + 1);
+        let median = out.pop().expect("at least two thrown out");
+        let mut rest = splitsort(vs, out, compare);
+        rest.push(median);
+        rest.extend(splitsort(vs, large, compare));
+
+        // merge the two ascending sequences of positions
+        let mut merged = Vec::with_capacity(kept.len() + rest.len());
+        let (mut a, mut b) = (kept.into_iter().peekable(), rest.into_iter().peekable());
+        while let (Some(&i), Some(&j)) = (a.peek(), b.peek()) {
+            if vs.compare(j, i, compare) == Ordering::Less {
+                merged.push(j);
+                b.next();
+            } else {
+                merged.push(i);
+                a.next();
+            }
+        }
+        merged.extend(a);
+        merged.extend(b);
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{test_it, test_it_by};
+
+    #[test]
+    fn test_adaptive_sorts_work() {
+        test_it(&natural::Algo);
+        test_it_by(&natural::Algo);
+        test_it(&splitsort::Algo);
+        test_it_by(&splitsort::Algo);
+
+        fn cmps<A: Sort>(go: A, mut xs: Vec<usize>) -> usize {
+            let mut cmps = 0;
+            go.sort_by(&mut xs, |a, b| {
+                cmps += 1;
+                a.cmp(b)
+            });
+            cmps
+        }
+        // one pass over sorted input; one swap costs splitsort a merge more
+        let sorted: Vec<usize> = (0..1000).collect();
+        let mut nearly = sorted.clone();
+        nearly.swap(100, 900);
+        assert_eq!(cmps(natural::Algo, sorted.clone()), 999);
+        assert_eq!(cmps(splitsort::Algo, sorted), 999);
+        assert!(cmps(splitsort::Algo, nearly) < 2 * 1000);
+    }
+}
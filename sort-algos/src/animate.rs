@@ -0,0 +1,106 @@
+use std::{fmt::Write as _, io, thread, time::Duration};
+
+use crate::{
+    registry,
+    tracked::{Event, Recorder},
+};
+
+const RESET: &str = "\x1b[0m";
+const COMPARE: &str = "\x1b[33m";
+const SWAP: &str = "\x1b[31m";
+const WRITE: &str = "\x1b[32m";
+
+/// Draws `vs` as columns of bars, `height` rows tall and scaled to the
+/// largest value, with the positions in `lit` drawn in `color`.
+pub fn frame(vs: &[usize], lit: &[usize], color: &str, height: usize) -> String {
+    let max = vs.iter().copied().max().unwrap_or(0).max(1);
+    let mut out = String::new();
+    for row in (0..height).rev() {
+        for (i, &v) in vs.iter().enumerate() {
+            // rounded up so every non-zero value shows
+            let bar = (v * height).div_ceil(max);
+            let cell = if bar > row { '█' } else { ' ' };
+            if lit.contains(&i) {
+                let _ = write!(out, "{color}{cell}{RESET}");
+            } else {
+                out.push(cell);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Plays `events` over `vs` on a terminal, one frame per event, pausing
+/// `delay` between frames. Comparisons show in yellow, swaps in red and
+/// writes in green.
+pub fn play<W>(
+    out: &mut W,
+    vs: &[usize],
+    events: &[Event<usize>],
+    height: usize,
+    delay: Duration,
+) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let mut vs = vs.to_vec();
+    let (mut cmps, mut swaps, mut writes) = (0, 0, 0);
+    // clear the screen and hide the cursor
+    write!(out, "\x1b[2J\x1b[?25l")?;
+    write!(out, "\x1b[H{}", frame(&vs, &[], RESET, height))?;
+    for e in events {
+        let (lit, color, label) = match *e {
+            Event::Compare(i, j) => {
+                cmps += 1;
+                (vec![i, j], COMPARE, format!("compare {i} {j}"))
+            }
+            Event::Swap(i, j) => {
+                swaps += 1;
+                vs.swap(i, j);
+                (vec![i, j], SWAP, format!("swap {i} {j}"))
+            }
+            Event::Write(i, v) => {
+                writes += 1;
+                vs[i] = v;
+                (vec![i], WRITE, format!("write {i}"))
+            }
+        };
+        write!(out, "\x1b[H{}", frame(&vs, &lit, color, height))?;
+        writeln!(
+            out,
+            "\x1b[K{label:<16} compares {cmps:>6}  swaps {swaps:>6}  writes {writes:>6}"
+        )?;
+        out.flush()?;
+        thread::sleep(delay);
+    }
+    write!(out, "\x1b[H{}", frame(&vs, &[], RESET, height))?;
+    // show the cursor again
+    writeln!(out, "\x1b[?25h")
+}
+
+/// Runs the algorithm named `algo` in the [`registry`] on `vs` and returns
+/// what it did, or `None` for unknown and unobserved algorithms.
+pub fn record(algo: &str, vs: &mut [usize]) -> Option<Vec<Event<usize>>> {
+    let algo = registry::find(algo).filter(|a| a.observed)?;
+    let r = Recorder::new();
+    algo.algo.run(vs, &Ord::cmp, &r);
+    Some(r.into_events())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animate_draws_bars() {
+        assert_eq!(frame(&[0, 2, 1], &[], "", 2), " █ \n ██\n");
+        assert_eq!(frame(&[1], &[0], "<", 1), format!("<█{}\n", "\x1b[0m"));
+
+        let mut xs = vec![3, 1, 2];
+        let events = record("timsort", &mut xs).unwrap();
+        assert_eq!(xs, [1, 2, 3]);
+        assert!(!events.is_empty());
+        assert!(record("par_merge", &mut xs).is_none());
+    }
+}
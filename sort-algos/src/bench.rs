@@ -0,0 +1,292 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    presorted,
+    registry::{self, Algorithm, Family},
+    tracked::Counts,
+};
+
+/// Shape of a generated input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dist {
+    /// uniformly random `usize`
+    Random,
+    /// already in order
+    Sorted,
+    /// in descending order
+    Reversed,
+    /// only `FEW_UNIQUE` distinct values
+    FewUnique,
+    /// ranks drawn with probability proportional to `1 / rank^ZIPF_S`
+    Zipf,
+    /// ascending runs of `SAWTOOTH` values
+    Sawtooth,
+    /// ascending up to the middle, descending after
+    OrganPipe,
+}
+
+const FEW_UNIQUE: usize = 16;
+const ZIPF_S: f64 = 1.1;
+const SAWTOOTH: usize = 64;
+
+impl Dist {
+    pub fn generate(self, n: usize, rand: &mut impl Rng) -> Vec<usize> {
+        match self {
+            Dist::Random => (0..n).map(|_| rand.gen()).collect(),
+            Dist::Sorted => (0..n).collect(),
+            Dist::Reversed => (0..n).rev().collect(),
+            Dist::FewUnique => (0..n).map(|_| rand.gen_range(0..FEW_UNIQUE)).collect(),
+            Dist::Zipf => {
+                let cdf: Vec<f64> = (1..=n)
+                    .scan(0.0, |acc, k| {
+                        *acc += (k as f64).powf(-ZIPF_S);
+                        Some(*acc)
+                    })
+                    .collect();
+                let total = cdf.last().copied().unwrap_or_default();
+                (0..n)
+                    .map(|_| {
+                        let u = rand.gen::<f64>() * total;
+                        cdf.partition_point(|&c| c < u).min(n - 1)
+                    })
+                    .collect()
+            }
+            Dist::Sawtooth => (0..n).map(|i| i % SAWTOOTH).collect(),
+            Dist::OrganPipe => (0..n).map(|i| if i < n / 2 { i } else { n - i }).collect(),
+        }
+    }
+}
+
+/// Swaps random pairs until about `fraction` of `vs` has moved, which
+/// takes ordered inputs smoothly towards random ones.
+pub fn perturb<T>(vs: &mut [T], fraction: f64, rand: &mut impl Rng) {
+    if vs.len() < 2 {
+        return;
+    }
+    let swaps = (fraction.clamp(0.0, 1.0) * vs.len() as f64 / 2.0).round() as usize;
+    for _ in 0..swaps {
+        let (i, j) = (rand.gen_range(0..vs.len()), rand.gen_range(0..vs.len()));
+        vs.swap(i, j);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub algos: Vec<String>,
+    pub dists: Vec<Dist>,
+    pub sizes: Vec<usize>,
+    /// fractions of each input [`perturb`]ed after it is generated
+    pub disorder: Vec<f64>,
+    pub trials: usize,
+    pub seed: u64,
+    /// thread counts tried for the parallel sorts
+    pub threads: Vec<usize>,
+}
+
+/// One timed sort.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub algo: String,
+    pub dist: Dist,
+    pub n: usize,
+    pub disorder: f64,
+    /// the [`presorted`] measures of the input
+    pub inversions: u64,
+    pub runs: usize,
+    pub rem: usize,
+    pub osc: u64,
+    pub trial: usize,
+    pub seed: u64,
+    pub threads: Option<usize>,
+    pub cmps: usize,
+    /// swaps and element moves, for observed algorithms only
+    pub swaps: Option<usize>,
+    pub moves: Option<usize>,
+    pub nanos: u64,
+    /// single-threaded time over this one, for the parallel sorts
+    pub speedup: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Measure {
+    cmps: usize,
+    swaps: Option<usize>,
+    moves: Option<usize>,
+    took: Duration,
+}
+
+/// Runs `algo` on a copy of `input`, counting comparisons in the
+/// comparator, which works across threads too.
+fn measure(algo: &Algorithm<usize>, input: &[usize]) -> Measure {
+    let mut values = input.to_vec();
+    let cmps = AtomicUsize::new(0);
+    let counts = Counts::default();
+    let took = Instant::now();
+    algo.algo.run(
+        &mut values,
+        &|a, b| {
+            cmps.fetch_add(1, AtomicOrdering::Relaxed);
+            a.cmp(b)
+        },
+        &counts,
+    );
+    let took = took.elapsed();
+    assert!(algo.verify(input, &values), "{} failed", algo.name);
+    Measure {
+        cmps: cmps.into_inner(),
+        swaps: algo.observed.then(|| counts.swaps()),
+        moves: algo.observed.then(|| counts.moves()),
+        took,
+    }
+}
+
+/// Runs `algo` on a pool of `threads` threads.
+fn measure_par(algo: &Algorithm<usize>, input: &[usize], threads: usize) -> Measure {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("thread pool");
+    pool.install(|| measure(algo, input))
+}
+
+/// Runs every configured algorithm on every distribution, size, disorder
+/// and trial, handing each measurement to `emit` as soon as it is taken.
+/// Algorithms are looked up in the [`registry`](crate::registry).
+pub fn run(config: &Config, mut emit: impl FnMut(Record)) {
+    let algos: Vec<_> = config
+        .algos
+        .iter()
+        .map(|name| registry::find(name).unwrap_or_else(|| panic!("unknown algorithm {name}")))
+        .collect();
+    for &dist in &config.dists {
+        for &n in &config.sizes {
+            for (&disorder, trial) in config.disorder.iter().flat_map(|d| {
+                // every disorder level gets the same trial seeds
+                (0..config.trials).map(move |t| (d, t))
+            }) {
+                let seed = config.seed.wrapping_add(trial as u64);
+                let mut rand = StdRng::seed_from_u64(seed);
+                let mut input = dist.generate(n, &mut rand);
+                perturb(&mut input, disorder, &mut rand);
+                let measures = presorted::measure(&input);
+
+                for algo in &algos {
+                    // (threads, measurement, speedup)
+                    let rows: Vec<(Option<usize>, Measure, Option<f64>)> =
+                        if algo.family == Family::Parallel {
+                            // once per thread count, relative to one thread
+                            let base = measure_par(algo, &input, 1);
+                            config
+                                .threads
+                                .iter()
+                                .map(|&t| {
+                                    let m = if t == 1 {
+                                        base
+                                    } else {
+                                        measure_par(algo, &input, t)
+                                    };
+                                    let speedup = base.took.as_secs_f64() / m.took.as_secs_f64();
+                                    (Some(t), m, Some(speedup))
+                                })
+                                .collect()
+                        } else {
+                            vec![(None, measure(algo, &input), None)]
+                        };
+
+                    for (threads, m, speedup) in rows {
+                        emit(Record {
+                            algo: algo.name.to_string(),
+                            dist,
+                            n,
+                            disorder,
+                            inversions: measures.inversions,
+                            runs: measures.runs,
+                            rem: measures.rem,
+                            osc: measures.osc,
+                            trial,
+                            seed,
+                            threads,
+                            cmps: m.cmps,
+                            swaps: m.swaps,
+                            moves: m.moves,
+                            nanos: m.took.as_nanos() as u64,
+                            speedup,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plot, registry};
+
+    #[test]
+    fn test_bench_runs() {
+        use clap::ValueEnum;
+        use registry::Family;
+
+        let config = Config {
+            algos: registry::names().into_iter().map(String::from).collect(),
+            dists: Dist::value_variants().to_vec(),
+            sizes: vec![0, 50],
+            disorder: vec![0.0, 0.5],
+            trials: 2,
+            seed: 42,
+            threads: vec![1, 2],
+        };
+        let mut records = Vec::new();
+        run(&config, |r| records.push(r));
+        // parallel sorts report once per thread count
+        let rows: usize = registry::algorithms::<usize>()
+            .iter()
+            .map(|a| if a.family == Family::Parallel { 2 } else { 1 })
+            .sum();
+        assert_eq!(
+            records.len(),
+            rows * Dist::value_variants().len() * 2 * 2 * 2
+        );
+        // sorted inputs only become unsorted when perturbed
+        for r in records.iter().filter(|r| r.dist == Dist::Sorted) {
+            assert_eq!(r.inversions == 0, r.disorder == 0.0 || r.n == 0);
+        }
+
+        // the same seed generates the same inputs
+        let mut again = Vec::new();
+        run(&config, |r| again.push(r));
+        for (a, b) in records.iter().zip(&again) {
+            assert_eq!(
+                (&a.algo, a.n, a.seed, a.cmps),
+                (&b.algo, b.n, b.seed, b.cmps)
+            );
+        }
+
+        // the plot reads what the bench writes
+        let mut w = csv::Writer::from_writer(Vec::new());
+        for r in &records {
+            w.serialize(r).unwrap();
+        }
+        let csv = w.into_inner().unwrap();
+        let read = csv::Reader::from_reader(&csv[..])
+            .deserialize()
+            .collect::<Result<Vec<Record>, _>>()
+            .unwrap();
+        assert_eq!(read.len(), records.len());
+        let svg = plot::svg(&read);
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        for name in ["inversions", "runs", "rem", "osc", "splitsort"] {
+            assert!(svg.contains(name), "{name}");
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+use crate::registry::{Comparison, DynSort};
+
+/// Sorts newline-delimited records that need not fit in memory. Records are
+/// compared as bytes, without their newline; every record in the output
+/// ends with one.
+///
+/// Chunks of at most `memory` bytes are sorted with `seq`, which must sort
+/// rather than select, and spilled to files in `temp_dir`, which are then
+/// merged `ways` at a time until one run is left. A merge never has more
+/// than `ways` runs open, and their read buffers share `memory`.
+pub struct External<S> {
+    pub seq: S,
+    pub memory: usize,
+    pub ways: usize,
+    pub temp_dir: PathBuf,
+}
+
+impl Default for External<Comparison<crate::timsort::Algo>> {
+    fn default() -> Self {
+        External {
+            seq: Comparison(crate::timsort::Algo),
+            memory: 64 << 20,
+            ways: 16,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+/// What a sort did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub records: usize,
+    /// sorted chunks spilled to disk
+    pub runs: usize,
+    /// rounds of merging before the output was written, including the last
+    pub passes: usize,
+}
+
+type Record = Vec<u8>;
+
+// a record is charged the bytes allocated for it plus the `Vec` holding it
+const OVERHEAD: usize = mem::size_of::<Record>();
+
+// below this, reading a run takes a system call every few records
+const MIN_BUFFER: usize = 1 << 10;
+
+impl<S> External<S>
+where
+    S: DynSort<Record>,
+{
+    pub fn sort<R, W>(&self, input: R, mut output: W) -> io::Result<Stats>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let mut stats = Stats::default();
+        let mut runs = self.spill(input, &mut stats)?;
+        stats.runs = runs.len();
+
+        // merge down to `ways` runs so the last pass can write the output
+        let ways = self.ways.max(2);
+        // one buffer per run read, and one for the run written
+        let buffer = (self.memory / (ways + 1)).max(MIN_BUFFER);
+        while runs.len() > ways {
+            let mut merged = Vec::new();
+            for group in runs.chunks(ways) {
+                let run = self.create_run()?;
+                let w = File::create(&run.path)?;
+                merge(group, buffer, &mut BufWriter::with_capacity(buffer, w))?;
+                merged.push(run);
+            }
+            runs = merged;
+            stats.passes += 1;
+        }
+        merge(&runs, buffer, &mut output)?;
+        stats.passes += 1;
+        output.flush()?;
+        Ok(stats)
+    }
+
+    /// Reads `input` a chunk at a time and writes each chunk, sorted, to
+    /// its own run.
+    fn spill<R>(&self, mut input: R, stats: &mut Stats) -> io::Result<Vec<Run>>
+    where
+        R: BufRead,
+    {
+        let mut runs = Vec::new();
+        let mut chunk: Vec<Record> = Vec::new();
+        let mut used = 0;
+        loop {
+            let mut record = Vec::new();
+            let eof = input.read_until(b'\n', &mut record)? == 0;
+            if !eof {
+                if record.last() == Some(&b'\n') {
+                    record.pop();
+                }
+                used += record.capacity() + OVERHEAD;
+                chunk.push(record);
+                stats.records += 1;
+            }
+            // a chunk always takes at least one record, however long
+            if (eof || used >= self.memory) && !chunk.is_empty() {
+                self.seq.run(&mut chunk, &Ord::cmp, &());
+                let run = self.create_run()?;
+                let mut w = BufWriter::new(File::create(&run.path)?);
+                for record in chunk.drain(..) {
+                    w.write_all(&record)?;
+                    w.write_all(b"\n")?;
+                }
+                w.flush()?;
+                runs.push(run);
+                used = 0;
+            }
+            if eof {
+                return Ok(runs);
+            }
+        }
+    }
+
+    fn create_run(&self) -> io::Result<Run> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = self
+            .temp_dir
+            .join(format!("sort-algos-{}-{id}.run", std::process::id()));
+        File::create(&path)?;
+        Ok(Run { path })
+    }
+}
+
+/// A sorted temp file, removed when dropped.
+struct Run {
+    path: PathBuf,
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// K-way merges `runs` into `output` through a min-heap holding the next
+/// record of each run, reading each through a buffer of `buffer` bytes.
+/// Ties go to the earlier run, which keeps the merge stable.
+fn merge<W>(runs: &[Run], buffer: usize, output: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut readers = runs
+        .iter()
+        .map(|run| File::open(&run.path).map(|f| BufReader::with_capacity(buffer, f)))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let next = |r: &mut BufReader<File>| -> io::Result<Option<Record>> {
+        let mut record = Vec::new();
+        if r.read_until(b'\n', &mut record)? == 0 {
+            return Ok(None);
+        }
+        record.pop();
+        Ok(Some(record))
+    };
+
+    let mut heap = BinaryHeap::with_capacity(readers.len());
+    for (i, r) in readers.iter_mut().enumerate() {
+        if let Some(record) = next(r)? {
+            heap.push(Reverse((record, i)));
+        }
+    }
+    while let Some(Reverse((record, i))) = heap.pop() {
+        output.write_all(&record)?;
+        output.write_all(b"\n")?;
+        if let Some(record) = next(&mut readers[i])? {
+            heap.push(Reverse((record, i)));
+        }
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{quicksort, registry};
+
+    #[test]
+    fn test_external_sort() {
+        use rand::Rng;
+
+        let dir = std::env::temp_dir().join(format!("sort-algos-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut rand = rand::thread_rng();
+        let lines: Vec<String> = (0..2000)
+            .map(|_| rand.gen_range(0..500).to_string())
+            .chain(["".to_string(), "\r".to_string()])
+            .collect();
+        let input = lines.join("\n");
+
+        // a tiny memory limit and 3 ways force several merge passes
+        let sorter = External {
+            seq: registry::Comparison(quicksort::Algo),
+            memory: 1024,
+            ways: 3,
+            temp_dir: dir.clone(),
+        };
+        let mut out = Vec::new();
+        let stats = sorter.sort(input.as_bytes(), &mut out).unwrap();
+        let mut expected = lines.clone();
+        expected.sort();
+        assert_eq!(String::from_utf8(out).unwrap(), expected.join("\n") + "\n");
+        assert_eq!(stats.records, lines.len());
+        assert!(stats.runs > 9 && stats.passes > 2, "{stats:?}");
+        // no pass merged more than 3 runs at once
+        assert!(3usize.pow(stats.passes as u32) >= stats.runs, "{stats:?}");
+        // every run was cleaned up
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let mut out = Vec::new();
+        let stats = sorter.sort(&b""[..], &mut out).unwrap();
+        assert_eq!(
+            (out.len(), stats),
+            (
+                0,
+                Stats {
+                    records: 0,
+                    runs: 0,
+                    passes: 1
+                }
+            )
+        );
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
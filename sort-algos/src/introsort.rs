@@ -0,0 +1,74 @@
+use super::*;
+use crate::{network, quicksort::partition, select::heapsort};
+
+/// Quicksort on a median-of-three pivot that falls back to heapsort after
+/// `2 log2(n)` levels, with sorting networks for slices of at most
+/// `network::MAX` elements.
+pub struct Algo;
+
+/// The same introsort with insertion sort for the small slices.
+pub struct Plain;
+
+impl Sort for Algo {
+    const STABLE: bool = false;
+
+    fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        let limit = 2 * vs.len().max(1).ilog2() as usize;
+        introsort(&mut Tracked::new(vs, observer), &mut compare, limit, true);
+    }
+}
+
+impl Sort for Plain {
+    const STABLE: bool = false;
+
+    fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        let limit = 2 * vs.len().max(1).ilog2() as usize;
+        introsort(&mut Tracked::new(vs, observer), &mut compare, limit, false);
+    }
+}
+
+fn introsort<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F, limit: usize, network: bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    if vs.len() <= network::MAX {
+        network::small_sort(vs, compare, network);
+        return;
+    }
+    if limit == 0 {
+        heapsort(vs, compare);
+        return;
+    }
+
+    let pivot = median_of_three(vs, compare);
+    if pivot != 0 {
+        vs.swap(0, pivot);
+    }
+    let at = partition(vs, compare);
+    introsort(&mut vs.slice(..at), compare, limit - 1, network);
+    introsort(&mut vs.slice(at + 1..), compare, limit - 1, network);
+}
+
+/// Position of the median of the first, middle and last elements.
+fn median_of_three<T, F, O>(vs: &Tracked<T, O>, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let (a, b, c) = (0, vs.len() / 2, vs.len() - 1);
+    let mut less = |i, j| vs.compare(i, j, compare) == Ordering::Less;
+    match (less(a, b), less(b, c), less(a, c)) {
+        (true, true, _) | (false, false, _) => b,
+        (true, false, true) | (false, true, false) => c,
+        _ => a,
+    }
+}
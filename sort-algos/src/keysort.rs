@@ -0,0 +1,447 @@
+use std::cmp::Ordering;
+
+use crate::tracked::{Observer, Tracked};
+
+/// A key that can be split into byte-sized digits, most significant first.
+pub trait RadixKey {
+    /// Number of digits in every key of this type, `None` for keys of
+    /// varying length such as byte strings.
+    const WIDTH: Option<usize>;
+
+    /// The `i`th digit, or `None` once the key has ended. An ended key
+    /// orders before any key that still has digits.
+    fn digit(&self, i: usize) -> Option<u8>;
+
+    /// A monotone estimate of where the key falls, used to spread keys
+    /// over buckets. Defaults to the leading digits read as a fraction.
+    fn rank(&self) -> f64 {
+        (0..6)
+            .map_while(|i| self.digit(i))
+            .enumerate()
+            .map(|(i, d)| d as f64 / 256f64.powi(i as i32 + 1))
+            .sum()
+    }
+}
+
+macro_rules! radix_key_uint {
+    ($($t:ty),*) => {
+        $(
+            impl RadixKey for $t {
+                const WIDTH: Option<usize> = Some(std::mem::size_of::<$t>());
+
+                fn digit(&self, i: usize) -> Option<u8> {
+                    self.to_be_bytes().get(i).copied()
+                }
+
+                fn rank(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+radix_key_uint!(u8, u16, u32, u64, u128, usize);
+
+macro_rules! radix_key_float {
+    ($($t:ty),*) => {
+        $(
+            impl RadixKey for $t {
+                const WIDTH: Option<usize> = Some(std::mem::size_of::<$t>());
+
+                fn digit(&self, i: usize) -> Option<u8> {
+                    // flip the sign bit of positives and every bit of
+                    // negatives so the bytes follow `total_cmp`
+                    let bits = self.to_bits();
+                    let sign = 1 << (std::mem::size_of::<$t>() * 8 - 1);
+                    let mask = if bits & sign != 0 { !0 } else { sign };
+                    (bits ^ mask).to_be_bytes().get(i).copied()
+                }
+
+                fn rank(&self) -> f64 {
+                    match self {
+                        v if v.is_nan() && v.is_sign_negative() => f64::NEG_INFINITY,
+                        v if v.is_nan() => f64::INFINITY,
+                        v => *v as f64,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+radix_key_float!(f32, f64);
+
+impl RadixKey for [u8] {
+    const WIDTH: Option<usize> = None;
+
+    fn digit(&self, i: usize) -> Option<u8> {
+        self.get(i).copied()
+    }
+}
+
+impl RadixKey for Vec<u8> {
+    const WIDTH: Option<usize> = None;
+
+    fn digit(&self, i: usize) -> Option<u8> {
+        self.get(i).copied()
+    }
+}
+
+impl RadixKey for str {
+    const WIDTH: Option<usize> = None;
+
+    fn digit(&self, i: usize) -> Option<u8> {
+        self.as_bytes().get(i).copied()
+    }
+}
+
+impl RadixKey for String {
+    const WIDTH: Option<usize> = None;
+
+    fn digit(&self, i: usize) -> Option<u8> {
+        self.as_bytes().get(i).copied()
+    }
+}
+
+impl<K> RadixKey for &K
+where
+    K: RadixKey + ?Sized,
+{
+    const WIDTH: Option<usize> = K::WIDTH;
+
+    fn digit(&self, i: usize) -> Option<u8> {
+        (**self).digit(i)
+    }
+
+    fn rank(&self) -> f64 {
+        (**self).rank()
+    }
+}
+
+/// Compares two keys digit by digit, starting at digit `from`.
+pub fn cmp_from<K>(a: &K, b: &K, from: usize) -> Ordering
+where
+    K: RadixKey + ?Sized,
+{
+    for i in from.. {
+        match (a.digit(i), b.digit(i)) {
+            (None, None) => return Ordering::Equal,
+            (x, y) if x != y => return x.cmp(&y),
+            _ => {}
+        }
+    }
+    unreachable!()
+}
+
+/// Sorts by extracting a `RadixKey` from each element instead of comparing
+/// elements. Every algorithm here is stable.
+pub trait KeySort {
+    /// Whether elements with equal keys keep their relative order.
+    const STABLE: bool;
+
+    /// Returns the sorted order of `keys`: position `i` of the sorted
+    /// sequence holds `keys[order[i]]`.
+    fn order<K>(&self, keys: &[K]) -> Vec<usize>
+    where
+        K: RadixKey;
+
+    fn sort<T>(&self, slice: &mut [T])
+    where
+        T: RadixKey,
+    {
+        let order = self.order(slice);
+        permute(&mut Tracked::new(slice, &()), order);
+    }
+
+    fn sort_by_key<T, K, F>(&self, slice: &mut [T], key: F)
+    where
+        K: RadixKey,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by_key_observed(slice, key, &());
+    }
+
+    /// Same as `sort_by_key`, reporting the swaps that put elements in
+    /// place to `observer`.
+    fn sort_by_key_observed<T, K, F, O>(&self, slice: &mut [T], key: F, observer: &O)
+    where
+        K: RadixKey,
+        F: FnMut(&T) -> K,
+        O: Observer<T>,
+    {
+        let keys: Vec<_> = slice.iter().map(key).collect();
+        let order = self.order(&keys);
+        permute(&mut Tracked::new(slice, observer), order);
+    }
+}
+
+/// Moves `vs[order[i]]` to `vs[i]` by following the cycles of `order`.
+pub(crate) fn permute<T, O>(vs: &mut Tracked<T, O>, mut order: Vec<usize>)
+where
+    O: Observer<T>,
+{
+    for start in 0..vs.len() {
+        let mut cur = start;
+        while order[cur] != cur {
+            let next = order[cur];
+            // mark `cur` as done
+            order[cur] = cur;
+            if next == start {
+                break;
+            }
+            vs.swap(cur, next);
+            cur = next;
+        }
+    }
+}
+
+/// Stable counting pass: reorders `from` into `to` by `bucket`, which maps
+/// an index to one of `buckets` buckets. Returns the bucket start offsets.
+pub(crate) fn counting_pass(
+    from: &[usize],
+    to: &mut [usize],
+    buckets: usize,
+    mut bucket: impl FnMut(usize) -> usize,
+) -> Vec<usize> {
+    let mut starts = vec![0; buckets + 1];
+    for &i in from {
+        starts[bucket(i) + 1] += 1;
+    }
+    for b in 0..buckets {
+        starts[b + 1] += starts[b];
+    }
+    let mut next = starts.clone();
+    for &i in from {
+        let b = bucket(i);
+        to[next[b]] = i;
+        next[b] += 1;
+    }
+    starts
+}
+
+/// Stable insertion sort of indices by their keys, from digit `from` on.
+fn insertion_by_digits<K>(idx: &mut [usize], keys: &[K], from: usize)
+where
+    K: RadixKey,
+{
+    for unsort in 1..idx.len() {
+        let (sort, rest) = idx.split_at(unsort);
+        let pos = sort
+            .partition_point(|&j| cmp_from(&keys[j], &keys[rest[0]], from) != Ordering::Greater);
+        idx[pos..=unsort].rotate_right(1);
+    }
+}
+
+pub mod radix_lsd {
+    use super::*;
+
+    pub struct Algo;
+
+    impl KeySort for Algo {
+        const STABLE: bool = true;
+
+        fn order<K>(&self, keys: &[K]) -> Vec<usize>
+        where
+            K: RadixKey,
+        {
+            let Some(width) = K::WIDTH else {
+                // byte strings have no fixed last digit to start from
+                return radix_msd::Algo.order(keys);
+            };
+
+            let mut idx: Vec<usize> = (0..keys.len()).collect();
+            let mut aux = vec![0; keys.len()];
+            for d in (0..width).rev() {
+                let digit = |i: usize| keys[i].digit(d).unwrap_or(0) as usize;
+                // skip passes where every key has the same digit
+                if let Some(&first) = idx.first() {
+                    let first = digit(first);
+                    if idx.iter().all(|&i| digit(i) == first) {
+                        continue;
+                    }
+                }
+                counting_pass(&idx, &mut aux, 256, digit);
+                std::mem::swap(&mut idx, &mut aux);
+            }
+            idx
+        }
+    }
+}
+
+pub mod radix_msd {
+    use super::*;
+
+    pub struct Algo;
+
+    // buckets at most this size are finished with insertion sort
+    const CUTOFF: usize = 32;
+
+    impl KeySort for Algo {
+        const STABLE: bool = true;
+
+        fn order<K>(&self, keys: &[K]) -> Vec<usize>
+        where
+            K: RadixKey,
+        {
+            let mut idx: Vec<usize> = (0..keys.len()).collect();
+            let mut aux = vec![0; keys.len()];
+            msd(&mut idx, &mut aux, keys, 0);
+            idx
+        }
+    }
+
+    fn msd<K>(idx: &mut [usize], aux: &mut [usize], keys: &[K], d: usize)
+    where
+        K: RadixKey,
+    {
+        if idx.len() <= CUTOFF {
+            insertion_by_digits(idx, keys, d);
+            return;
+        }
+
+        // bucket 0 holds keys that ended before digit `d`
+        let starts = counting_pass(idx, aux, 257, |i| {
+            keys[i].digit(d).map_or(0, |b| b as usize + 1)
+        });
+        idx.copy_from_slice(aux);
+
+        for b in 1..=256 {
+            let (lo, hi) = (starts[b], starts[b + 1]);
+            if hi - lo > 1 {
+                msd(&mut idx[lo..hi], &mut aux[lo..hi], keys, d + 1);
+            }
+        }
+    }
+}
+
+pub mod counting {
+    use super::*;
+
+    pub struct Algo;
+
+    // widest span of key values that gets its own count table
+    const MAX_RANGE: u64 = 1 << 20;
+
+    impl KeySort for Algo {
+        const STABLE: bool = true;
+
+        fn order<K>(&self, keys: &[K]) -> Vec<usize>
+        where
+            K: RadixKey,
+        {
+            let value = |k: &K| (0..8).fold(0u64, |v, i| v << 8 | k.digit(i).unwrap_or(0) as u64);
+            let (min, max) = match K::WIDTH {
+                Some(width) if width <= 8 => keys
+                    .iter()
+                    .map(|k| value(k) >> ((8 - width) * 8))
+                    .fold((u64::MAX, 0), |(lo, hi), v| (lo.min(v), hi.max(v))),
+                _ => (0, u64::MAX),
+            };
+            if keys.is_empty() || max - min >= MAX_RANGE {
+                return radix_lsd::Algo.order(keys);
+            }
+
+            let shift = (8 - K::WIDTH.unwrap_or(8)) * 8;
+            let idx: Vec<usize> = (0..keys.len()).collect();
+            let mut out = vec![0; keys.len()];
+            counting_pass(&idx, &mut out, (max - min + 1) as usize, |i| {
+                ((value(&keys[i]) >> shift) - min) as usize
+            });
+            out
+        }
+    }
+}
+
+pub mod bucket {
+    use super::*;
+
+    pub struct Algo;
+
+    impl KeySort for Algo {
+        const STABLE: bool = true;
+
+        fn order<K>(&self, keys: &[K]) -> Vec<usize>
+        where
+            K: RadixKey,
+        {
+            let ranks: Vec<f64> = keys.iter().map(RadixKey::rank).collect();
+            let (lo, hi) = ranks
+                .iter()
+                .filter(|r| r.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &r| {
+                    (lo.min(r), hi.max(r))
+                });
+
+            // one bucket per key; uniform ranks leave about one key in each.
+            // `as usize` saturates, which sends infinities to the end buckets
+            let buckets = keys.len().max(1);
+            let bucket = |i: usize| {
+                let at = (ranks[i] - lo) / (hi - lo) * buckets as f64;
+                (at as usize).min(buckets - 1)
+            };
+
+            let idx: Vec<usize> = (0..keys.len()).collect();
+            let mut out = vec![0; keys.len()];
+            let starts = counting_pass(&idx, &mut out, buckets, bucket);
+            for b in 0..buckets {
+                insertion_by_digits(&mut out[starts[b]..starts[b + 1]], keys, 0);
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_keys<Algo>(go: &Algo)
+    where
+        Algo: KeySort,
+    {
+        use rand::Rng;
+        let mut rand = rand::thread_rng();
+        for n in [0, 1, 2, 10, 100, 1000] {
+            let mut xs: Vec<u64> = (0..n).map(|_| rand.gen()).collect();
+            let mut ys = xs.clone();
+            ys.sort();
+            go.sort(&mut xs);
+            assert_eq!(xs, ys);
+
+            // small key range, with the index to check stability
+            let mut xs: Vec<(u16, usize)> = (0..n).map(|i| (rand.gen_range(0..10), i)).collect();
+            let mut ys = xs.clone();
+            ys.sort_by_key(|&(k, _)| k);
+            go.sort_by_key(&mut xs, |&(k, _)| k);
+            assert_eq!(xs, ys);
+
+            let mut xs: Vec<String> = (0..n)
+                .map(|_| {
+                    let len = rand.gen_range(0..6);
+                    (0..len).map(|_| rand.gen_range('a'..='d')).collect()
+                })
+                .collect();
+            let mut ys = xs.clone();
+            ys.sort();
+            go.sort(&mut xs);
+            assert_eq!(xs, ys);
+
+            let mut xs: Vec<f64> = (0..n).map(|_| rand.gen_range(-1e3..1e3)).collect();
+            xs.extend([0.0, -0.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN]);
+            let mut ys = xs.clone();
+            ys.sort_by(f64::total_cmp);
+            go.sort(&mut xs);
+            let bits = |vs: &[f64]| vs.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+            assert_eq!(bits(&xs), bits(&ys));
+        }
+    }
+
+    #[test]
+    fn test_keysorts_work() {
+        test_keys(&radix_lsd::Algo);
+        test_keys(&radix_msd::Algo);
+        test_keys(&counting::Algo);
+        test_keys(&bucket::Algo);
+    }
+}
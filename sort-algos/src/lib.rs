@@ -0,0 +1,480 @@
+use std::cmp::Ordering;
+
+use tracked::{Observer, Tracked};
+
+pub trait Sort {
+    /// Whether elements that compare equal keep their relative order.
+    const STABLE: bool;
+
+    /// Sorts `slice`, reporting every comparison, swap and element move to
+    /// `observer`.
+    fn sort_by_observed<T, F, O>(&self, slice: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>;
+
+    fn sort_by<T, F>(&self, slice: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_observed(slice, compare, &());
+    }
+
+    fn sort<T>(&self, slice: &mut [T])
+    where
+        T: Ord,
+    {
+        self.sort_by(slice, T::cmp);
+    }
+
+    fn sort_by_key<T, K, F>(&self, slice: &mut [T], mut key: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(slice, |a, b| key(a).cmp(&key(b)));
+    }
+}
+
+pub mod bubble {
+    use super::*;
+
+    pub struct Algo;
+
+    impl Sort for Algo {
+        const STABLE: bool = true;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            let mut vs = Tracked::new(vs, observer);
+            match vs.len() {
+                0 => return,
+                _ => {}
+            }
+
+            let mut swapped = true;
+            while swapped {
+                swapped = false;
+                for i in 0..(vs.len() - 1) {
+                    if vs.compare(i, i + 1, &mut compare) == Ordering::Greater {
+                        vs.swap(i, i + 1);
+                        swapped = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub mod insertion {
+
+    use super::*;
+
+    pub struct Algo;
+
+    impl Sort for Algo {
+        const STABLE: bool = true;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            let mut vs = Tracked::new(vs, observer);
+            // [ sort | not sort ]
+            for unsort in 1..vs.len() {
+                // binary search for the first element greater than `vs[unsort]`
+                let (mut lo, mut hi) = (0, unsort);
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    match vs.compare(mid, unsort, &mut compare) {
+                        Ordering::Greater => hi = mid,
+                        _ => lo = mid + 1,
+                    }
+                }
+                vs.rotate_right(lo..unsort + 1, 1);
+            }
+        }
+    }
+}
+
+pub mod selection {
+
+    use super::*;
+
+    pub struct Algo;
+
+    impl Sort for Algo {
+        const STABLE: bool = false;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            let mut vs = Tracked::new(vs, observer);
+            // [ sort | not sort ]
+            for unsorted in 0..vs.len() {
+                let mut smallest_in_rest = unsorted;
+                for i in unsorted + 1..vs.len() {
+                    if vs.compare(i, smallest_in_rest, &mut compare) == Ordering::Less {
+                        smallest_in_rest = i;
+                    }
+                }
+
+                if unsorted != smallest_in_rest {
+                    vs.swap(unsorted, smallest_in_rest);
+                }
+            }
+        }
+    }
+}
+
+pub mod quicksort {
+
+    use super::*;
+
+    /// Quicksort on the first element, with sorting networks for slices of
+    /// at most `network::MAX` elements.
+    pub struct Algo;
+
+    /// The same quicksort with insertion sort for the small slices.
+    pub struct Plain;
+
+    impl Sort for Algo {
+        const STABLE: bool = false;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            quicksort(Tracked::new(vs, observer), &mut compare, true);
+        }
+    }
+
+    impl Sort for Plain {
+        const STABLE: bool = false;
+
+        fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            quicksort(Tracked::new(vs, observer), &mut compare, false);
+        }
+    }
+
+    fn quicksort<T, F, O>(mut vs: Tracked<T, O>, compare: &mut F, network: bool)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        if vs.len() <= network::MAX {
+            network::small_sort(&mut vs, compare, network);
+            return;
+        }
+        let left = partition(&mut vs, compare);
+        quicksort(vs.slice(..left), compare, network);
+        quicksort(vs.slice(left + 1..), compare, network);
+    }
+
+    /// Partitions `vs` around `vs[0]` and returns where the pivot ends up:
+    /// nothing before it is greater and nothing after it is less.
+    pub(crate) fn partition<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        let mut is_less = |vs: &Tracked<T, O>, i, j| vs.compare(i, j, compare) == Ordering::Less;
+
+        match vs.len() {
+            0 | 1 => return 0,
+            2 if is_less(vs, 1, 0) => {
+                vs.swap(0, 1);
+                return 1;
+            }
+            _ => {}
+        }
+
+        // the pivot sits at 0: [ pivot | < pivot | not seen | > pivot ]
+        let mut left = 1;
+        let mut right = vs.len() - 1;
+
+        while left < right {
+            if is_less(vs, left, 0) {
+                left += 1;
+            } else if is_less(vs, 0, right) {
+                right -= 1;
+            } else {
+                vs.swap(left, right);
+                left += 1;
+                right -= 1;
+            }
+        }
+
+        if left == right && is_less(vs, left, 0) {
+            if is_less(vs, left, left - 1) {
+                vs.swap(left - 1, left);
+            }
+            left += 1;
+        }
+        let left = left - 1;
+
+        // place the pivot at its final location
+        if left > 0 {
+            vs.swap(0, left);
+        }
+        left
+    }
+}
+
+pub mod adaptive;
+pub mod animate;
+pub mod bench;
+pub mod external;
+pub mod introsort;
+pub mod keysort;
+pub mod mergesort;
+pub mod network;
+pub mod parallel;
+pub mod plot;
+pub mod presorted;
+pub mod registry;
+pub mod select;
+pub mod timsort;
+pub mod tracked;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keysort::KeySort;
+    pub(crate) fn test_it<Algo>(go: &Algo)
+    where
+        Algo: Sort,
+    {
+        let mut vecs = vec![
+            vec![],
+            vec![2, 1],
+            vec![1, 3, 2],
+            vec![2, 3, 1],
+            vec![5, 1, 3, 2, 4],
+            vec![5, 1, 2, 4, 3],
+            vec![4, 3, 5],
+            vec![5, 4, 3, 5],
+            vec![6, 4, 3, 5],
+            vec![1, 4, 3, 5],
+        ];
+        for xs in &mut vecs {
+            let ys = {
+                let mut xs = xs.clone();
+                xs.sort();
+                xs
+            };
+            go.sort(xs);
+            assert_eq!(xs, &ys)
+        }
+    }
+
+    pub(crate) fn test_it_by<Algo>(go: &Algo)
+    where
+        Algo: Sort,
+    {
+        let mut xs = vec![5, 1, 3, 2, 4, 3];
+        go.sort_by(&mut xs, |a, b| b.cmp(a));
+        assert_eq!(xs, [5, 4, 3, 3, 2, 1]);
+
+        let mut xs = vec![("c", 2), ("a", 3), ("b", 1)];
+        go.sort_by_key(&mut xs, |&(_, n)| n);
+        assert_eq!(xs, [("b", 1), ("c", 2), ("a", 3)]);
+
+        let mut xs = vec![2.5, -1.0, 0.0, 10.25, -3.5];
+        go.sort_by(&mut xs, f64::total_cmp);
+        assert_eq!(xs, [-3.5, -1.0, 0.0, 2.5, 10.25]);
+    }
+
+    #[test]
+    fn test_bubble_works() {
+        test_it(&bubble::Algo);
+        test_it_by(&bubble::Algo);
+    }
+
+    #[test]
+    fn test_insertion_works() {
+        test_it(&insertion::Algo);
+        test_it_by(&insertion::Algo);
+    }
+
+    #[test]
+    fn test_selection_works() {
+        test_it(&selection::Algo);
+        test_it_by(&selection::Algo);
+    }
+
+    #[test]
+    fn test_quicksort_works() {
+        test_it(&quicksort::Algo);
+        test_it_by(&quicksort::Algo);
+    }
+
+    mod props {
+        use super::*;
+        use keysort::{bucket, counting, radix_lsd, radix_msd};
+        use parallel::ParSort;
+        use proptest::{collection::vec, prelude::*};
+
+        const MAX_LEN: usize = 300;
+
+        /// Random, duplicate-heavy and adversarial inputs.
+        fn inputs() -> impl Strategy<Value = Vec<u32>> {
+            let len = 0..MAX_LEN;
+            prop_oneof![
+                vec(any::<u32>(), len.clone()),
+                vec(0..4u32, len.clone()),
+                len.clone().prop_map(|n| (0..n as u32).collect()),
+                len.clone().prop_map(|n| (0..n as u32).rev().collect()),
+                len.clone()
+                    .prop_map(|n| (0..n as u32).map(|i| i.min(n as u32 - i)).collect()),
+                len.clone()
+                    .prop_map(|n| (0..n as u32).map(|i| i % 7).collect()),
+                (len.clone(), any::<u32>()).prop_map(|(n, v)| vec![v; n]),
+                // sorted except for a few random slots
+                (vec(any::<u32>(), len), any::<u64>()).prop_map(|(mut xs, seed)| {
+                    use rand::{rngs::StdRng, Rng, SeedableRng};
+                    xs.sort();
+                    let mut rand = StdRng::seed_from_u64(seed);
+                    for _ in 0..xs.len() / 16 {
+                        let i = rand.gen_range(0..xs.len());
+                        xs[i] = rand.gen();
+                    }
+                    xs
+                }),
+            ]
+        }
+
+        /// (key, original index) pairs with few distinct keys.
+        fn pairs() -> impl Strategy<Value = Vec<(u8, usize)>> {
+            vec(0..8u8, 0..MAX_LEN)
+                .prop_map(|ks| ks.into_iter().enumerate().map(|(i, k)| (k, i)).collect())
+        }
+
+        fn expected(xs: &[u32]) -> Vec<u32> {
+            let mut ys = xs.to_vec();
+            ys.sort();
+            ys
+        }
+
+        // equal keys must keep their index order when the algorithm is
+        // stable, and the keys must be in order either way
+        fn check_stable(stable: bool, xs: &[(u8, usize)], sorted: &[(u8, usize)]) {
+            let mut ys = xs.to_vec();
+            ys.sort_by_key(|&(k, _)| k);
+            if stable {
+                assert_eq!(sorted, ys);
+            } else {
+                let keys = |vs: &[(u8, usize)]| vs.iter().map(|&(k, _)| k).collect::<Vec<_>>();
+                assert_eq!(keys(sorted), keys(&ys));
+            }
+        }
+
+        fn sorts<A: Sort>(go: A, xs: &[u32], pairs: &[(u8, usize)]) {
+            let mut vs = xs.to_vec();
+            go.sort(&mut vs);
+            assert_eq!(vs, expected(xs));
+
+            let mut vs = pairs.to_vec();
+            go.sort_by_key(&mut vs, |&(k, _)| k);
+            check_stable(A::STABLE, pairs, &vs);
+        }
+
+        fn key_sorts<A: KeySort>(go: A, xs: &[u32], pairs: &[(u8, usize)]) {
+            let mut vs = xs.to_vec();
+            go.sort(&mut vs);
+            assert_eq!(vs, expected(xs));
+
+            let mut vs = pairs.to_vec();
+            go.sort_by_key(&mut vs, |&(k, _)| k);
+            check_stable(A::STABLE, pairs, &vs);
+        }
+
+        fn par_sorts<A: ParSort>(go: A, xs: &[u32], pairs: &[(u8, usize)]) {
+            let mut vs = xs.to_vec();
+            go.sort(&mut vs);
+            assert_eq!(vs, expected(xs));
+
+            let mut vs = pairs.to_vec();
+            go.sort_by_key(&mut vs, |&(k, _)| k);
+            check_stable(A::STABLE, pairs, &vs);
+        }
+
+        proptest! {
+            #[test]
+            fn prop_sorts_match_std(xs in inputs(), pairs in pairs()) {
+                sorts(bubble::Algo, &xs, &pairs);
+                sorts(insertion::Algo, &xs, &pairs);
+                sorts(selection::Algo, &xs, &pairs);
+                sorts(quicksort::Algo, &xs, &pairs);
+                sorts(quicksort::Plain, &xs, &pairs);
+                sorts(mergesort::Algo, &xs, &pairs);
+                sorts(mergesort::Plain, &xs, &pairs);
+                sorts(introsort::Algo, &xs, &pairs);
+                sorts(introsort::Plain, &xs, &pairs);
+                sorts(timsort::Algo, &xs, &pairs);
+                sorts(adaptive::natural::Algo, &xs, &pairs);
+                sorts(adaptive::splitsort::Algo, &xs, &pairs);
+            }
+
+            #[test]
+            fn prop_key_sorts_match_std(xs in inputs(), pairs in pairs()) {
+                key_sorts(radix_lsd::Algo, &xs, &pairs);
+                key_sorts(radix_msd::Algo, &xs, &pairs);
+                key_sorts(counting::Algo, &xs, &pairs);
+                key_sorts(bucket::Algo, &xs, &pairs);
+            }
+
+            #[test]
+            fn prop_selects_match_std(xs in inputs(), at in any::<prop::sample::Index>(), k in 0..MAX_LEN) {
+                use select::{introselect, median_of_medians, quickselect, Select};
+                let sorted = expected(&xs);
+                if !xs.is_empty() {
+                    let n = at.index(xs.len());
+                    let check = |vs: &[u32]| {
+                        assert_eq!(vs[n], sorted[n]);
+                        assert!(vs[..n].iter().all(|v| *v <= vs[n]));
+                        assert!(vs[n..].iter().all(|v| *v >= vs[n]));
+                    };
+                    let mut vs = xs.clone();
+                    quickselect::Algo.select_nth(&mut vs, n);
+                    check(&vs);
+                    let mut vs = xs.clone();
+                    introselect::Algo.select_nth(&mut vs, n);
+                    check(&vs);
+                    let mut vs = xs.clone();
+                    median_of_medians::Algo.select_nth(&mut vs, n);
+                    check(&vs);
+                }
+
+                let k = k.min(xs.len());
+                let mut vs = xs.clone();
+                select::partial_sort(&mut vs, k);
+                assert_eq!(vs[..k], sorted[..k]);
+                let mut rest = vs[k..].to_vec();
+                rest.sort();
+                assert_eq!(rest, sorted[k..]);
+                assert_eq!(select::top_k(xs.iter().copied(), k), sorted[..k]);
+            }
+
+            #[test]
+            fn prop_par_sorts_match_std(xs in inputs(), pairs in pairs()) {
+                // small cutoffs so the parallel paths run on short inputs
+                par_sorts(parallel::merge::Algo { seq: timsort::Algo, cutoff: 8 }, &xs, &pairs);
+                par_sorts(parallel::quicksort::Algo { seq: quicksort::Algo, cutoff: 8 }, &xs, &pairs);
+                par_sorts(parallel::sample::Algo { seq: timsort::Algo, cutoff: 8 }, &xs, &pairs);
+                par_sorts(parallel::sample::Algo { seq: insertion::Algo, cutoff: 8 }, &xs, &pairs);
+            }
+        }
+    }
+}
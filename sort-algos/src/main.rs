@@ -1,347 +1,294 @@
-use std::fmt::Debug;
-
-trait Sort {
-    fn sort<T>(&self, slice: &mut [T])
-    where
-        T: Debug,
-        T: Ord;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::{builder::PossibleValuesParser, Parser, Subcommand, ValueEnum};
+use rand::{rngs::StdRng, SeedableRng};
+use sort_algos::{
+    animate,
+    bench::{self, Config, Dist},
+    external::External,
+    plot,
+    registry::{self, Goal},
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
 }
 
-mod bubble {
-    use super::*;
-
-    pub struct Algo;
-
-    impl Sort for Algo {
-        fn sort<T>(&self, vs: &mut [T])
-        where
-            T: Ord,
-        {
-            match vs.len() {
-                0 => return,
-                _ => {}
-            }
-
-            let mut swapped = true;
-            while swapped {
-                swapped = false;
-                for i in 0..(vs.len() - 1) {
-                    if vs[i] > vs[i + 1] {
-                        vs.swap(i, i + 1);
-                        swapped = true;
-                    }
-                }
-            }
-        }
-    }
+/// Benchmark and watch the sorting algorithms.
+#[derive(Debug, Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-mod insertion {
-
-    use super::*;
-
-    pub struct Algo;
-
-    impl Sort for Algo {
-        fn sort<T>(&self, vs: &mut [T])
-        where
-            T: Ord,
-        {
-            // [ sort | not sort ]
-            for unsort in 1..vs.len() {
-                let sort = vs[..unsort]
-                    .binary_search(&vs[unsort])
-                    .map_or_else(|x| x, |x| x);
-                vs[sort..=unsort].rotate_right(1);
-            }
-        }
-    }
+#[derive(Debug, Subcommand)]
+enum Command {
+    Bench(BenchArgs),
+    Animate(AnimateArgs),
+    External(ExternalArgs),
+    Plot(PlotArgs),
+    /// List the algorithms with their properties.
+    List,
 }
 
-mod selection {
-
-    use super::*;
-
-    pub struct Algo;
-
-    impl Sort for Algo {
-        fn sort<T>(&self, vs: &mut [T])
-        where
-            T: Ord,
-        {
-            // [ sort | not sort ]
-            for unsorted in 0..vs.len() {
-                let (smallest_in_rest, _) = vs[unsorted..]
-                    .iter()
-                    .enumerate()
-                    .min_by_key(|&(_, v)| v)
-                    .expect("slice isn't empty");
-                let smallest_in_rest = unsorted + smallest_in_rest;
-
-                if unsorted != smallest_in_rest {
-                    vs.swap(unsorted, smallest_in_rest);
-                }
-            }
-        }
-    }
+/// Benchmark the sorting algorithms on generated inputs.
+#[derive(Debug, clap::Args)]
+struct BenchArgs {
+    /// algorithms to run, every one when absent
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(registry::names()))]
+    algos: Vec<String>,
+    /// input distributions
+    #[arg(long, value_delimiter = ',', default_value = "random")]
+    dists: Vec<Dist>,
+    /// input sizes
+    #[arg(long, value_delimiter = ',', default_values_t = [10, 100, 1000, 10000])]
+    sizes: Vec<usize>,
+    /// fractions of each input moved by random swaps, from 0 (as generated)
+    /// to 1 (shuffled)
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    disorder: Vec<f64>,
+    /// trials per algorithm, distribution and size
+    #[arg(long, default_value_t = 1)]
+    trials: usize,
+    /// seed of the first trial, random when absent; trial `i` uses `seed + i`
+    #[arg(long)]
+    seed: Option<u64>,
+    /// thread counts for the parallel sorts, defaults to 1 and every core
+    #[arg(long, value_delimiter = ',')]
+    threads: Vec<usize>,
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
 }
 
-mod quicksort {
-
-    use super::*;
-
-    pub struct Algo;
-
-    impl Sort for Algo {
-        fn sort<T>(&self, vs: &mut [T])
-        where
-            T: Ord,
-            T: Debug,
-        {
-            fn inc(x: &mut usize) {
-                *x += 1;
-            }
-
-            fn dec(x: &mut usize) {
-                *x -= 1;
-            }
-
-            fn shift(msg: &str, fun: fn(&mut usize), pos: &mut usize) {
-                #[cfg(test)]
-                print!("\t{msg}: {pos}");
-                fun(pos);
-                #[cfg(test)]
-                println!(" => {pos}");
-            }
-
-            fn swap_lr<T>(
-                cond: impl Fn() -> bool,
-                pivot: &mut T,
-                rest: &mut [T],
-                left: &mut usize,
-                right: &mut usize,
-            ) where
-                T: Debug,
-            {
-                #[cfg(test)]
-                println!("\tswap_lr({pivot:?}, {rest:?}, {left}, {right})?");
-                if cond() {
-                    rest.swap(*left, *right);
-                    shift("\tleft", inc, left);
-                    shift("\tright", dec, right);
-                }
-                #[cfg(test)]
-                println!("\t... => {rest:?}");
-            }
-
-            #[cfg(test)]
-            println!("quicksort({:?})", vs);
-            match vs.len() {
-                0 | 1 => {
-                    #[cfg(test)]
-                    println!("...  => {:?}", vs);
-                    return;
-                }
-
-                2 => {
-                    if vs[0] > vs[1] {
-                        vs.swap(0, 1);
-                        #[cfg(test)]
-                        println!("...  => {:?}", vs);
-                        return;
-                    }
-                }
-                _ => {}
-            }
-            let (pivot, rest) = vs.split_first_mut().expect("slice is non-empty");
-            let mut left = 0;
-            let mut right = rest.len() - 1;
-
-            while left < right {
-                #[cfg(test)]
-                println!("pivot={pivot:?}, rest={rest:?}, bound=({left},{right})");
-                if &rest[left] < pivot {
-                    shift("left", inc, &mut left);
-                } else if &rest[right] > pivot {
-                    shift("right", dec, &mut right);
-                } else {
-                    swap_lr(|| true, pivot, rest, &mut left, &mut right);
-                }
-                #[cfg(test)]
-                println!("... => rest={rest:?}, bound=({left},{right})");
-            }
+/// Replay a sort on the terminal, one comparison, swap or write per frame.
+#[derive(Debug, clap::Args)]
+struct AnimateArgs {
+    /// algorithm to watch; the parallel sorts and top_k cannot be watched
+    #[arg(long, value_parser = PossibleValuesParser::new(registry::names()), default_value = "quicksort")]
+    algo: String,
+    /// number of elements, one column each
+    #[arg(long, default_value_t = 40)]
+    n: usize,
+    #[arg(long, value_enum, default_value_t = Dist::Random)]
+    dist: Dist,
+    /// random when absent
+    #[arg(long)]
+    seed: Option<u64>,
+    /// rows of the tallest bar
+    #[arg(long, default_value_t = 16)]
+    height: usize,
+    /// pause between frames
+    #[arg(long, default_value_t = 50)]
+    delay_ms: u64,
+}
 
-            if left == right {
-                if &rest[left] < pivot {
-                    self.sort(&mut rest[left - 1..left + 1]);
-                    shift("left", inc, &mut left);
-                }
-            }
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// quicksort pivots on the first element and so recurses once per element
+// on ordered inputs; give it room
+const STACK_SIZE: usize = 1 << 30;
+
+/// Sort newline-delimited records that may not fit in memory.
+#[derive(Debug, clap::Args)]
+struct ExternalArgs {
+    /// read from stdin when absent
+    input: Option<PathBuf>,
+    /// write to stdout when absent
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// algorithm sorting each chunk in memory; selections cannot be used
+    #[arg(long, value_parser = PossibleValuesParser::new(registry::names()), default_value = "timsort")]
+    algo: String,
+    /// bytes of records held in memory at once
+    #[arg(long, default_value_t = 64 << 20)]
+    memory: usize,
+    /// runs merged at once
+    #[arg(long, default_value_t = 16)]
+    ways: usize,
+    /// where sorted runs are spilled, the system temp directory when absent
+    #[arg(long)]
+    temp_dir: Option<PathBuf>,
+}
 
-            // place the pivot at its final location
-            vs.swap(0, left);
-            #[cfg(test)]
-            println!("...  => {:?}", vs);
+/// Plot comparisons against the presortedness of the inputs, as SVG, from
+/// the CSV written by `bench`.
+#[derive(Debug, clap::Args)]
+struct PlotArgs {
+    /// read from stdin when absent
+    input: Option<PathBuf>,
+    /// write to stdout when absent
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// input size plotted, the largest in the input when absent
+    #[arg(long)]
+    n: Option<usize>,
+}
 
-            let (left, right) = vs.split_at_mut(left);
-            self.sort(left);
-            self.sort(&mut right[1..]);
-        }
-    }
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match cli.command {
+            Command::Bench(args) => run_bench(args),
+            Command::Animate(args) => run_animate(args),
+            Command::External(args) => run_external(args),
+            Command::Plot(args) => run_plot(args),
+            Command::List => run_list(),
+        })?
+        .join()
+        .expect("worker thread panicked")
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    fn test_it<Algo>(go: &Algo)
-    where
-        Algo: Sort,
-    {
-        let mut vecs = vec![
-            vec![],
-            vec![2, 1],
-            vec![1, 3, 2],
-            vec![2, 3, 1],
-            vec![5, 1, 3, 2, 4],
-            vec![5, 1, 2, 4, 3],
-            vec![4, 3, 5],
-            vec![5, 4, 3, 5],
-            vec![6, 4, 3, 5],
-            vec![1, 4, 3, 5],
-        ];
-        for xs in &mut vecs {
-            let ys = {
-                let mut xs = xs.clone();
-                xs.sort();
-                xs
-            };
-            go.sort(xs);
-            assert_eq!(xs, &ys)
-        }
+fn run_bench(args: BenchArgs) -> Result<(), Error> {
+    let mut threads = args.threads;
+    if threads.is_empty() {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        threads = vec![1, cores];
+        threads.dedup();
     }
-
-    #[test]
-    fn test_bubble_works() {
-        test_it(&bubble::Algo);
+    let mut algos = args.algos;
+    if algos.is_empty() {
+        algos = registry::names().into_iter().map(String::from).collect();
     }
+    let config = Config {
+        algos,
+        dists: args.dists,
+        sizes: args.sizes,
+        disorder: args.disorder,
+        trials: args.trials,
+        seed: args.seed.unwrap_or_else(rand::random),
+        threads,
+    };
 
-    #[test]
-    fn test_insertion_works() {
-        test_it(&insertion::Algo);
+    match args.format {
+        Format::Csv => {
+            let mut w = csv::Writer::from_writer(io::stdout());
+            let mut result = Ok(());
+            bench::run(&config, |r| {
+                if result.is_ok() {
+                    result = w.serialize(r).and_then(|_| Ok(w.flush()?));
+                }
+            });
+            result?;
+        }
+        Format::Json => {
+            let mut records = Vec::new();
+            bench::run(&config, |r| records.push(r));
+            serde_json::to_writer_pretty(io::stdout(), &records)?;
+            println!();
+        }
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_selection_works() {
-        test_it(&selection::Algo);
-    }
+fn run_animate(args: AnimateArgs) -> Result<(), Error> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    // small values keep the bars readable
+    let input: Vec<usize> = args
+        .dist
+        .generate(args.n, &mut StdRng::seed_from_u64(seed))
+        .into_iter()
+        .map(|v| v % (args.n.max(1) * 4))
+        .collect();
+    let mut sorted = input.clone();
+    let events = animate::record(&args.algo, &mut sorted)
+        .ok_or_else(|| format!("{} cannot be animated", args.algo))?;
+    animate::play(
+        &mut io::stdout().lock(),
+        &input,
+        &events,
+        args.height,
+        Duration::from_millis(args.delay_ms),
+    )?;
+    Ok(())
+}
 
-    #[test]
-    fn test_quicksort_works() {
-        test_it(&quicksort::Algo);
+fn run_external(args: ExternalArgs) -> Result<(), Error> {
+    let algo = registry::find(&args.algo).ok_or("unknown algorithm")?;
+    if algo.goal != Goal::Sort {
+        return Err(format!("{} does not sort", algo.name).into());
     }
+    let sorter = External {
+        seq: algo.algo,
+        memory: args.memory,
+        ways: args.ways,
+        temp_dir: args.temp_dir.unwrap_or_else(std::env::temp_dir),
+    };
+    let output: Box<dyn io::Write> = match args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    let stats = match args.input {
+        Some(path) => sorter.sort(BufReader::new(File::open(path)?), output)?,
+        None => sorter.sort(io::stdin().lock(), output)?,
+    };
+    eprintln!(
+        "{} records, {} runs, {} merge passes",
+        stats.records, stats.runs, stats.passes
+    );
+    Ok(())
 }
 
-mod bench {
-    use super::*;
-    use std::{
-        cell::Cell,
-        rc::Rc,
-        time::{Duration, Instant},
+fn run_plot(args: PlotArgs) -> Result<(), Error> {
+    let input: Box<dyn io::Read> = match args.input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(io::stdin().lock()),
     };
-
-    use rand::Rng;
-
-    use crate::Sort;
-
-    #[derive(Debug, Clone)]
-    struct Eval<T> {
-        t: T,
-        cmps: Rc<Cell<usize>>,
+    let records = csv::Reader::from_reader(input)
+        .deserialize()
+        .collect::<Result<Vec<bench::Record>, _>>()?;
+    let n = args
+        .n
+        .or_else(|| records.iter().map(|r| r.n).max())
+        .ok_or("no records to plot")?;
+    let records: Vec<_> = records.into_iter().filter(|r| r.n == n).collect();
+    if records.is_empty() {
+        return Err(format!("no records with n = {n}").into());
     }
-
-    impl<T> Eq for Eval<T> where T: Eq {}
-
-    impl<T> PartialEq for Eval<T>
-    where
-        T: PartialEq,
-    {
-        fn eq(&self, other: &Self) -> bool {
-            self.t == other.t
-        }
+    let svg = plot::svg(&records);
+    match args.output {
+        Some(path) => std::fs::write(path, svg)?,
+        None => io::Write::write_all(&mut io::stdout().lock(), svg.as_bytes())?,
     }
-    impl<T> PartialOrd for Eval<T>
-    where
-        T: PartialOrd,
-    {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            self.cmps.set(self.cmps.get() + 1);
-            self.t.partial_cmp(&other.t)
-        }
-    }
-    impl<T> Ord for Eval<T>
-    where
-        T: Ord,
-    {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.partial_cmp(other).unwrap()
-        }
-    }
-
-    fn bench<T, A>(algo: A, values: &[Eval<T>], counter: &Cell<usize>) -> (usize, Duration)
-    where
-        T: Ord,
-        T: Clone,
-        T: Debug,
-        A: Sort,
-    {
-        let mut values: Vec<_> = values.to_vec();
-        counter.set(0);
-        let took = Instant::now();
-        algo.sort(&mut values);
-        let took = took.elapsed();
-        let count = counter.get();
-        for i in 1..values.len() {
-            assert!(values[i - 1] <= values[i]);
-        }
-        (count, took)
-    }
-
-    pub fn run() {
-        let mut rand = rand::thread_rng();
-        let counter = Rc::new(Cell::new(0));
-        for &n in &[0, 10, 100, 1000, 10000, 100000] {
-            for _ in 0..1 {
-                let mut values = Vec::with_capacity(n);
-                for _ in 0..n {
-                    values.push(Eval {
-                        t: rand.gen::<usize>(),
-                        cmps: Rc::clone(&counter),
-                    });
-                }
-
-                println!("{}", "*".repeat(50));
+    Ok(())
+}
 
-                {
-                    let took = bench(crate::bubble::Algo, &values, &counter);
-                    println!("bubble {n} {took:?}");
-                }
-                {
-                    let took = bench(crate::insertion::Algo, &values, &counter);
-                    println!("insertion {n} {took:?}");
-                }
-                {
-                    let took = bench(crate::selection::Algo, &values, &counter);
-                    println!("selection {n} {took:?}");
-                }
-                {
-                    let took = bench(crate::quicksort::Algo, &values, &counter);
-                    println!("quicksort {n} {took:?}");
+fn run_list() -> Result<(), Error> {
+    println!(
+        "{:<18} {:<11} {:<7} {:<9} {:<8} {:<10} {:<8} space",
+        "name", "family", "stable", "in-place", "best", "average", "worst"
+    );
+    for a in registry::algorithms::<usize>() {
+        let c = a.complexity;
+        // stability only means something for a full sort
+        let stable = match a.goal {
+            Goal::Sort => {
+                if a.stable {
+                    "yes"
+                } else {
+                    "no"
                 }
             }
-        }
+            _ => "-",
+        };
+        println!(
+            "{:<18} {:<11} {:<7} {:<9} {:<8} {:<10} {:<8} {}",
+            a.name,
+            format!("{:?}", a.family).to_lowercase(),
+            stable,
+            if a.in_place { "yes" } else { "no" },
+            c.best,
+            c.average,
+            c.worst,
+            c.space,
+        );
     }
-}
-
-fn main() {
-    bench::run();
+    Ok(())
 }
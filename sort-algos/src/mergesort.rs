@@ -0,0 +1,109 @@
+use std::ptr;
+
+use super::*;
+use crate::{network, timsort::MergeHole};
+
+/// Top-down merge sort with sorting networks for slices of at most
+/// `network::MAX` elements. The networks make it unstable.
+pub struct Algo;
+
+/// The same merge sort with insertion sort for the small slices, which
+/// keeps it stable.
+pub struct Plain;
+
+impl Sort for Algo {
+    const STABLE: bool = false;
+
+    fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        let mut buf = Vec::with_capacity(vs.len() / 2);
+        mergesort(
+            &mut Tracked::new(vs, observer),
+            &mut buf,
+            &mut compare,
+            true,
+        );
+    }
+}
+
+impl Sort for Plain {
+    const STABLE: bool = true;
+
+    fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        let mut buf = Vec::with_capacity(vs.len() / 2);
+        mergesort(
+            &mut Tracked::new(vs, observer),
+            &mut buf,
+            &mut compare,
+            false,
+        );
+    }
+}
+
+// `buf` is scratch space; its length stays 0 so it never drops anything
+fn mergesort<T, F, O>(vs: &mut Tracked<T, O>, buf: &mut Vec<T>, compare: &mut F, network: bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    if vs.len() <= network::MAX {
+        network::small_sort(vs, compare, network);
+        return;
+    }
+    let mid = vs.len() / 2;
+    mergesort(&mut vs.slice(..mid), buf, compare, network);
+    mergesort(&mut vs.slice(mid..), buf, compare, network);
+    // the halves are already in order
+    if vs.compare(mid, mid - 1, compare) != Ordering::Less {
+        return;
+    }
+    merge(vs, mid, buf, compare);
+}
+
+/// Merges `vs[..mid]` and `vs[mid..]` front to back, buffering the left half.
+pub(crate) fn merge<T, F, O>(vs: &mut Tracked<T, O>, mid: usize, buf: &mut Vec<T>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let (observer, base) = (vs.observer(), vs.base());
+    let len = vs.len();
+    buf.reserve(mid);
+    let v = vs.untracked().as_mut_ptr();
+    let b = buf.as_mut_ptr();
+
+    // SAFETY: `buf` has room for `mid` elements. Every slot of `vs` is
+    // either initialized or covered by `hole`, which moves the rest of the
+    // left half into the gap when done or when `compare` panics.
+    unsafe {
+        ptr::copy_nonoverlapping(v, b, mid);
+        let mut hole = MergeHole {
+            start: b,
+            end: b.add(mid),
+            dest: v,
+        };
+        let mut right = mid;
+        while hole.start < hole.end && right < len {
+            // until the merge is reported, elements show where they started
+            let left = hole.start.offset_from(b) as usize;
+            observer.compare(base + right, base + left);
+            if compare(&*v.add(right), &*hole.start) == Ordering::Less {
+                ptr::copy_nonoverlapping(v.add(right), hole.dest, 1);
+                right += 1;
+            } else {
+                ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                hole.start = hole.start.add(1);
+            }
+            hole.dest = hole.dest.add(1);
+        }
+    }
+    vs.buffered(mid);
+    vs.written(0..len);
+}
@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+
+use crate::tracked::{Observer, Tracked};
+
+/// Longest slice a network is built for.
+pub const MAX: usize = 16;
+
+// comparators in the network for `MAX` elements, the largest one
+const MAX_PAIRS: usize = 63;
+
+/// A fixed sequence of compare-exchanges on positions `(i, j)`, `i < j`,
+/// that sorts any input of its size.
+#[derive(Debug, Clone, Copy)]
+struct Network {
+    pairs: [(u8, u8); MAX_PAIRS],
+    len: usize,
+}
+
+/// Batcher's odd-even merge sort for the next power of two, keeping the
+/// comparators that stay below `n`. It is optimal up to 8 elements and at
+/// most three comparators over the best known networks up to 16.
+const fn batcher(n: usize) -> Network {
+    let mut net = Network {
+        pairs: [(0, 0); MAX_PAIRS],
+        len: 0,
+    };
+    let size = n.next_power_of_two();
+    let mut p = 1;
+    while p < size {
+        let mut k = p;
+        while k >= 1 {
+            let mut j = k % p;
+            while j + k < size {
+                let mut i = 0;
+                while i < k && i + j + k < size {
+                    let (a, b) = (i + j, i + j + k);
+                    // only compare within one block of `2p` being merged
+                    if a / (2 * p) == b / (2 * p) && b < n {
+                        net.pairs[net.len] = (a as u8, b as u8);
+                        net.len += 1;
+                    }
+                    i += 1;
+                }
+                j += 2 * k;
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+    net
+}
+
+const NETWORKS: [Network; MAX + 1] = {
+    let mut nets = [batcher(0); MAX + 1];
+    let mut n = 1;
+    while n <= MAX {
+        nets[n] = batcher(n);
+        n += 1;
+    }
+    nets
+};
+
+/// The comparators of the network sorting `n <= MAX` elements, in the
+/// order they run.
+pub fn comparators(n: usize) -> &'static [(u8, u8)] {
+    const TABLE: &[Network; MAX + 1] = &NETWORKS;
+    let net = &TABLE[n];
+    &net.pairs[..net.len]
+}
+
+/// Sorts `vs`, at most `MAX` long, with its network. Each comparator is a
+/// comparison followed by a branchless conditional swap; it is not stable.
+pub(crate) fn sort<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    // one copy per size, so each network unrolls into straight-line code
+    macro_rules! dispatch {
+        ($($n:literal)*) => {
+            match vs.len() {
+                $($n => sort_n::<_, _, _, $n>(vs, compare),)*
+                n => panic!("no network for {n} elements"),
+            }
+        };
+    }
+    dispatch!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16);
+}
+
+fn sort_n<T, F, O, const N: usize>(vs: &mut Tracked<T, O>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let net = const { NETWORKS[N] };
+    let mut exchange = |(i, j): (u8, u8)| {
+        let (i, j) = (i as usize, j as usize);
+        let swap = vs.compare(j, i, compare) == Ordering::Less;
+        vs.swap_if(i, j, swap);
+    };
+    // spelled out rather than looped, since `net.len` is a constant the
+    // untaken steps fold away
+    macro_rules! steps {
+        ($($k:literal)*) => {
+            $(if $k < net.len {
+                exchange(net.pairs[$k]);
+            })*
+        };
+    }
+    steps!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+        32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60
+        61 62
+    );
+}
+
+/// Straight insertion sort by adjacent swaps, the usual small-slice kernel
+/// that the networks replace.
+pub(crate) fn insertion_sort<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    for i in 1..vs.len() {
+        let mut j = i;
+        while j > 0 && vs.compare(j, j - 1, compare) == Ordering::Less {
+            vs.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+/// Base case of the divide-and-conquer sorts: the network for `vs` when
+/// `network` holds, insertion sort otherwise.
+pub(crate) fn small_sort<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F, network: bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    debug_assert!(vs.len() <= MAX);
+    if network {
+        sort(vs, compare);
+    } else {
+        insertion_sort(vs, compare);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        introsort, mergesort, quicksort,
+        tests::{test_it, test_it_by},
+        tracked, Sort,
+    };
+
+    #[test]
+    fn test_network_sorts_work() {
+        test_it(&quicksort::Plain);
+        test_it_by(&quicksort::Plain);
+        test_it(&mergesort::Algo);
+        test_it_by(&mergesort::Algo);
+        test_it(&mergesort::Plain);
+        test_it_by(&mergesort::Plain);
+        test_it(&introsort::Algo);
+        test_it_by(&introsort::Algo);
+        test_it(&introsort::Plain);
+        test_it_by(&introsort::Plain);
+    }
+
+    #[test]
+    fn test_networks_sort_all_inputs() {
+        // a network sorts everything iff it sorts every 0-1 input
+        let best = [0, 0, 1, 3, 5, 9, 12, 16, 19, 25, 29, 35, 39, 45, 51, 56, 60];
+        for (n, &best) in best.iter().enumerate() {
+            let pairs = comparators(n);
+            assert!(pairs.len() <= best + 3, "{n}: {}", pairs.len());
+            if n <= 8 {
+                assert_eq!(pairs.len(), best);
+            }
+            for bits in 0u32..1 << n {
+                let mut vs: Vec<_> = (0..n).map(|i| bits >> i & 1).collect();
+                sort(&mut tracked::Tracked::new(&mut vs, &()), &mut Ord::cmp);
+                assert!(vs.windows(2).all(|w| w[0] <= w[1]), "{n}: {bits:b}");
+            }
+        }
+
+        // on random input the networks spare comparisons over insertion sort
+        fn cmps<A: Sort>(go: A) -> usize {
+            use rand::{rngs::StdRng, Rng, SeedableRng};
+            let mut rand = StdRng::seed_from_u64(7);
+            let mut vs: Vec<u32> = (0..10_000).map(|_| rand.gen()).collect();
+            let mut count = 0;
+            go.sort_by(&mut vs, |a, b| {
+                count += 1;
+                a.cmp(b)
+            });
+            count
+        }
+        assert!(cmps(quicksort::Algo) < cmps(quicksort::Plain));
+        assert!(cmps(introsort::Algo) < cmps(introsort::Plain));
+    }
+}
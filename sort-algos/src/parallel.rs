@@ -0,0 +1,487 @@
+use std::{cmp::Ordering, ptr};
+
+use crate::Sort;
+
+/// Sorts that split their work across the rayon thread pool. Elements must
+/// be `Send + Sync` and the comparator `Sync`, so this is its own trait
+/// rather than an extension of [`Sort`].
+pub trait ParSort {
+    /// Whether elements that compare equal keep their relative order.
+    const STABLE: bool;
+
+    fn sort_by<T, F>(&self, slice: &mut [T], compare: F)
+    where
+        T: Send + Sync,
+        F: Fn(&T, &T) -> Ordering + Sync;
+
+    fn sort<T>(&self, slice: &mut [T])
+    where
+        T: Ord + Send + Sync,
+    {
+        self.sort_by(slice, T::cmp);
+    }
+
+    fn sort_by_key<T, K, F>(&self, slice: &mut [T], key: F)
+    where
+        T: Send + Sync,
+        K: Ord,
+        F: Fn(&T) -> K + Sync,
+    {
+        self.sort_by(slice, |a, b| key(a).cmp(&key(b)));
+    }
+}
+
+// slices at most this long are left to the sequential algorithm
+const CUTOFF: usize = 1 << 12;
+
+/// Raw pointer that may cross threads; callers hand out disjoint ranges.
+struct SendPtr<T>(*mut T);
+
+impl<T> Clone for SendPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SendPtr<T> {}
+
+unsafe impl<T: Send> Send for SendPtr<T> {}
+unsafe impl<T: Send> Sync for SendPtr<T> {}
+
+impl<T> SendPtr<T> {
+    // closures must capture the wrapper, not the bare pointer field
+    fn get(self) -> *mut T {
+        self.0
+    }
+
+    fn add(self, n: usize) -> Self {
+        // SAFETY: callers stay within the buffer they split
+        SendPtr(unsafe { self.0.add(n) })
+    }
+}
+
+pub mod merge {
+    use super::*;
+
+    /// Top-down merge sort: halves are sorted in parallel, then merged into
+    /// a scratch buffer by splitting both halves around a common element.
+    pub struct Algo<S> {
+        pub seq: S,
+        pub cutoff: usize,
+    }
+
+    impl Default for Algo<crate::timsort::Algo> {
+        fn default() -> Self {
+            Algo {
+                seq: crate::timsort::Algo,
+                cutoff: CUTOFF,
+            }
+        }
+    }
+
+    impl<S> ParSort for Algo<S>
+    where
+        S: Sort + Sync,
+    {
+        const STABLE: bool = S::STABLE;
+
+        fn sort_by<T, F>(&self, vs: &mut [T], compare: F)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            // never dropped through: its length stays 0
+            let mut buf = Vec::<T>::with_capacity(vs.len());
+            self.merge_sort(vs, SendPtr(buf.as_mut_ptr()), &compare);
+        }
+    }
+
+    impl<S> Algo<S>
+    where
+        S: Sort + Sync,
+    {
+        fn merge_sort<T, F>(&self, vs: &mut [T], buf: SendPtr<T>, compare: &F)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            if vs.len() <= self.cutoff.max(2) {
+                self.seq.sort_by(vs, compare);
+                return;
+            }
+
+            let mid = vs.len() / 2;
+            let (left, right) = vs.split_at_mut(mid);
+            rayon::join(
+                || self.merge_sort(left, buf, compare),
+                || self.merge_sort(right, buf.add(mid), compare),
+            );
+
+            let (left, right) = vs.split_at(mid);
+            self.merge(left, right, buf, compare);
+            // SAFETY: `merge` bit-copied every element of `vs` into `buf`
+            // without running a comparison afterwards, so moving them back
+            // cannot be interrupted by a panic.
+            unsafe { ptr::copy_nonoverlapping(buf.get(), vs.as_mut_ptr(), vs.len()) };
+        }
+
+        /// Bit-copies the stable merge of `a` and `b` into `dest`. The inputs
+        /// are left untouched, so a panicking comparator loses nothing.
+        fn merge<T, F>(&self, a: &[T], b: &[T], dest: SendPtr<T>, compare: &F)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            let is_less = |x: &T, y: &T| compare(x, y) == Ordering::Less;
+
+            if a.len() + b.len() <= self.cutoff.max(2) {
+                let (mut i, mut j) = (0, 0);
+                let dest = dest.get();
+                // SAFETY: `dest` has room for `a.len() + b.len()` elements
+                unsafe {
+                    while i < a.len() && j < b.len() {
+                        if is_less(&b[j], &a[i]) {
+                            ptr::copy_nonoverlapping(&b[j], dest.add(i + j), 1);
+                            j += 1;
+                        } else {
+                            ptr::copy_nonoverlapping(&a[i], dest.add(i + j), 1);
+                            i += 1;
+                        }
+                    }
+                    ptr::copy_nonoverlapping(a[i..].as_ptr(), dest.add(i + j), a.len() - i);
+                    ptr::copy_nonoverlapping(b[j..].as_ptr(), dest.add(i + j), b.len() - j);
+                }
+                return;
+            }
+
+            // split the longer run in half; equal elements of `b` stay after
+            // those of `a` on either side of the split
+            let (i, j) = if a.len() >= b.len() {
+                let i = a.len() / 2;
+                (i, b.partition_point(|x| is_less(x, &a[i])))
+            } else {
+                let j = b.len() / 2;
+                (a.partition_point(|x| !is_less(&b[j], x)), j)
+            };
+            rayon::join(
+                || self.merge(&a[..i], &b[..j], dest, compare),
+                || self.merge(&a[i..], &b[j..], dest.add(i + j), compare),
+            );
+        }
+    }
+}
+
+pub mod quicksort {
+    use super::*;
+
+    /// Quicksort with a median-of-three pivot and a three-way partition,
+    /// so runs of equal keys do not unbalance the recursion. Both sides are
+    /// forked with `rayon::join`; past `2 log2(n)` levels, or below the
+    /// cutoff, the slice goes to `seq`. Rayon's workers have small stacks,
+    /// so `seq` should bound its own depth whatever the input.
+    pub struct Algo<S> {
+        pub seq: S,
+        pub cutoff: usize,
+    }
+
+    impl Default for Algo<crate::introsort::Algo> {
+        fn default() -> Self {
+            Algo {
+                seq: crate::introsort::Algo,
+                cutoff: CUTOFF,
+            }
+        }
+    }
+
+    impl<S> ParSort for Algo<S>
+    where
+        S: Sort + Sync,
+    {
+        // partitioning moves equal elements past each other
+        const STABLE: bool = false;
+
+        fn sort_by<T, F>(&self, vs: &mut [T], compare: F)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            let limit = 2 * vs.len().max(1).ilog2() as usize;
+            self.quicksort(vs, &compare, limit);
+        }
+    }
+
+    impl<S> Algo<S>
+    where
+        S: Sort + Sync,
+    {
+        fn quicksort<T, F>(&self, vs: &mut [T], compare: &F, limit: usize)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            if vs.len() <= self.cutoff.max(2) || limit == 0 {
+                self.seq.sort_by(vs, compare);
+                return;
+            }
+
+            let (lt, gt) = partition(vs, compare);
+            let (less, rest) = vs.split_at_mut(lt);
+            rayon::join(
+                || self.quicksort(less, compare, limit - 1),
+                || self.quicksort(&mut rest[gt - lt..], compare, limit - 1),
+            );
+        }
+    }
+
+    /// Rearranges `vs` into `[ < pivot | == pivot | > pivot ]` and returns
+    /// the bounds of the middle part.
+    fn partition<T, F>(vs: &mut [T], compare: &F) -> (usize, usize)
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let (a, b, c) = (0, vs.len() / 2, vs.len() - 1);
+        let lt = |x: usize, y: usize| compare(&vs[x], &vs[y]) == Ordering::Less;
+        let median = if lt(a, b) == lt(b, c) {
+            b
+        } else if lt(a, b) == lt(a, c) {
+            c
+        } else {
+            a
+        };
+        vs.swap(0, median);
+
+        // [ pivot | < | == | not seen | > ]
+        let (pivot, rest) = vs.split_first_mut().expect("slice is non-empty");
+        let (mut lt, mut i, mut gt) = (0, 0, rest.len());
+        while i < gt {
+            match compare(&rest[i], pivot) {
+                Ordering::Less => {
+                    rest.swap(lt, i);
+                    lt += 1;
+                    i += 1;
+                }
+                Ordering::Equal => i += 1,
+                Ordering::Greater => {
+                    gt -= 1;
+                    rest.swap(i, gt);
+                }
+            }
+        }
+
+        // move the pivot between the smaller and the equal elements
+        vs.swap(0, lt);
+        (lt, gt + 1)
+    }
+}
+
+pub mod sample {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::{
+        keysort::{counting_pass, permute},
+        tracked::Tracked,
+    };
+
+    /// Sample sort: splitters drawn from a sorted random sample cut the
+    /// input into one bucket per task, and the buckets are sorted
+    /// independently. Elements equal to a splitter get a bucket of their
+    /// own, which needs no sorting, so duplicates cannot pile up in one
+    /// bucket. Equal keys always share a bucket, so a stable `seq` keeps it
+    /// stable.
+    pub struct Algo<S> {
+        pub seq: S,
+        pub cutoff: usize,
+    }
+
+    impl Default for Algo<crate::timsort::Algo> {
+        fn default() -> Self {
+            Algo {
+                seq: crate::timsort::Algo,
+                cutoff: CUTOFF,
+            }
+        }
+    }
+
+    // samples taken per bucket
+    const OVERSAMPLE: usize = 8;
+
+    impl<S> ParSort for Algo<S>
+    where
+        S: Sort + Sync,
+    {
+        const STABLE: bool = S::STABLE;
+
+        fn sort_by<T, F>(&self, vs: &mut [T], compare: F)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            let n = vs.len();
+            if n <= self.cutoff.max(2) {
+                self.seq.sort_by(vs, &compare);
+                return;
+            }
+
+            let buckets = (rayon::current_num_threads() * 4).max(2);
+            let (bucket, buckets) = self.split(vs, &compare, buckets);
+            let idx: Vec<usize> = (0..n).collect();
+            let mut order = vec![0; n];
+            let starts = counting_pass(&idx, &mut order, buckets, |i| bucket[i]);
+            permute(&mut Tracked::new(vs, &()), order);
+
+            let mut parts = Vec::with_capacity(buckets);
+            let mut rest = vs;
+            for b in 0..buckets {
+                let (part, tail) = rest.split_at_mut(starts[b + 1] - starts[b]);
+                parts.push(part);
+                rest = tail;
+            }
+            // the odd buckets hold the elements equal to a splitter
+            parts
+                .into_par_iter()
+                .step_by(2)
+                .for_each(|part| self.seq.sort_by(part, &compare));
+        }
+    }
+
+    impl<S> Algo<S>
+    where
+        S: Sort + Sync,
+    {
+        /// Assigns each element of `vs` a bucket, aiming for `buckets` of
+        /// them; returns the assignment and how many buckets it uses. With
+        /// `m` distinct splitters, bucket `2b` holds the elements between
+        /// splitters `b - 1` and `b`, and bucket `2b + 1` those equal to
+        /// splitter `b`.
+        pub(crate) fn split<T, F>(
+            &self,
+            vs: &[T],
+            compare: &F,
+            buckets: usize,
+        ) -> (Vec<usize>, usize)
+        where
+            T: Send + Sync,
+            F: Fn(&T, &T) -> Ordering + Sync,
+        {
+            let n = vs.len();
+            // seeded, so runs repeat, but random, so no input's period
+            // lines up with the sample
+            let mut rand = StdRng::seed_from_u64(n as u64);
+            let samples = (buckets * OVERSAMPLE).min(n);
+            let mut sample: Vec<usize> = (0..samples).map(|_| rand.gen_range(0..n)).collect();
+            self.seq
+                .sort_by(&mut sample, |&a, &b| compare(&vs[a], &vs[b]));
+            let mut splitters: Vec<&T> = (1..buckets)
+                .map(|b| &vs[sample[b * samples / buckets]])
+                .collect();
+            splitters.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+
+            let bucket = vs
+                .par_iter()
+                .map(|x| {
+                    let b = splitters.partition_point(|s| compare(s, x) == Ordering::Less);
+                    match splitters.get(b) {
+                        Some(s) if compare(s, x) == Ordering::Equal => 2 * b + 1,
+                        _ => 2 * b,
+                    }
+                })
+                .collect();
+            (bucket, 2 * splitters.len() + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timsort;
+
+    fn test_par<Algo>(go: &Algo)
+    where
+        Algo: ParSort,
+    {
+        use rand::Rng;
+        let mut rand = rand::thread_rng();
+        for n in [0, 1, 2, 3, 10, 100, 1000, 10000] {
+            for range in [4, usize::MAX] {
+                let mut xs: Vec<(usize, usize)> =
+                    (0..n).map(|i| (rand.gen_range(0..range), i)).collect();
+                let mut ys = xs.clone();
+                ys.sort_by_key(|&(k, _)| k);
+                go.sort_by_key(&mut xs, |&(k, _)| k);
+                // only compare keys, quicksort is not stable
+                assert!(xs.iter().zip(&ys).all(|(x, y)| x.0 == y.0));
+                xs.sort();
+                ys.sort();
+                assert_eq!(xs, ys);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_works() {
+        test_par(&merge::Algo::default());
+        test_par(&quicksort::Algo::default());
+        test_par(&sample::Algo::default());
+        // small cutoffs so the forking paths run too
+        test_par(&merge::Algo {
+            seq: timsort::Algo,
+            cutoff: 4,
+        });
+        test_par(&quicksort::Algo {
+            seq: crate::quicksort::Algo,
+            cutoff: 4,
+        });
+        test_par(&sample::Algo {
+            seq: timsort::Algo,
+            cutoff: 4,
+        });
+    }
+
+    #[test]
+    fn test_sample_sort_balances_buckets() {
+        use rand::Rng;
+
+        let mut rand = rand::thread_rng();
+        let n = 100_000;
+        let algo = sample::Algo::default();
+        for distinct in [1, 3, 100, n] {
+            let xs: Vec<usize> = (0..n).map(|_| rand.gen_range(0..distinct)).collect();
+            let (bucket, buckets) = algo.split(&xs, &usize::cmp, 16);
+            let mut sizes = vec![0; buckets];
+            for b in bucket {
+                sizes[b] += 1;
+            }
+            // however many duplicates, what is left to sort is spread out
+            let largest = sizes.iter().step_by(2).max().unwrap();
+            assert!(*largest <= n / 4, "{distinct}: {sizes:?}");
+        }
+    }
+
+    #[test]
+    fn test_par_quicksort_ordered_inputs() {
+        // ordered slices below the cutoff once recursed once per element,
+        // past the end of a rayon worker's stack
+        let n = 100_000;
+        let inputs: [Vec<usize>; 3] = [
+            (0..n).collect(),
+            (0..n).rev().collect(),
+            (0..n).map(|i| if i < n / 2 { i } else { n - i }).collect(),
+        ];
+        for threads in [1, 4] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("thread pool");
+            for input in &inputs {
+                let mut xs = input.clone();
+                let mut ys = input.clone();
+                ys.sort();
+                pool.install(|| quicksort::Algo::default().sort(&mut xs));
+                assert_eq!(xs, ys);
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Draws bench results as SVG: comparisons against each measure of
+//! [presortedness](crate::presorted), one panel per measure and one line
+//! per algorithm.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::bench::Record;
+
+/// A measure of the input on the x axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    Inversions,
+    Runs,
+    Rem,
+    Osc,
+}
+
+impl Measure {
+    pub const ALL: [Measure; 4] = [
+        Measure::Inversions,
+        Measure::Runs,
+        Measure::Rem,
+        Measure::Osc,
+    ];
+
+    pub fn of(self, r: &Record) -> f64 {
+        match self {
+            Measure::Inversions => r.inversions as f64,
+            Measure::Runs => r.runs as f64,
+            Measure::Rem => r.rem as f64,
+            Measure::Osc => r.osc as f64,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Measure::Inversions => "inversions",
+            Measure::Runs => "runs",
+            Measure::Rem => "rem",
+            Measure::Osc => "osc",
+        }
+    }
+}
+
+const PANEL_WIDTH: f64 = 480.0;
+const PANEL_HEIGHT: f64 = 320.0;
+// room for the axis labels around each plot area
+const MARGIN: f64 = 60.0;
+const LEGEND_WIDTH: f64 = 180.0;
+const COLORS: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+// once the colours run out they come round again with another dash pattern
+const DASHES: [&str; 3] = ["none", "6 3", "2 2"];
+
+/// The colour and dash pattern of series `i`, distinct for the first
+/// `COLORS.len() * DASHES.len()` series.
+fn style(i: usize) -> (&'static str, &'static str) {
+    (
+        COLORS[i % COLORS.len()],
+        DASHES[i / COLORS.len() % DASHES.len()],
+    )
+}
+
+/// `text` with the characters XML gives meaning to escaped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Plots the mean comparisons of every algorithm in `records` against each
+/// [`Measure`], in a two by two grid. Measures go on a log scale, offset by
+/// one so sorted inputs land on the axis.
+pub fn svg(records: &[Record]) -> String {
+    // algorithm -> the records for it, in order of first appearance
+    let mut algos: Vec<&str> = Vec::new();
+    let mut rows: BTreeMap<&str, Vec<&Record>> = BTreeMap::new();
+    for r in records {
+        if !rows.contains_key(r.algo.as_str()) {
+            algos.push(&r.algo);
+        }
+        rows.entry(&r.algo).or_default().push(r);
+    }
+
+    let width = 2.0 * PANEL_WIDTH + LEGEND_WIDTH;
+    let height = 2.0 * PANEL_HEIGHT;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="11">"#
+    );
+    let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    for (k, measure) in Measure::ALL.into_iter().enumerate() {
+        let x0 = (k % 2) as f64 * PANEL_WIDTH;
+        let y0 = (k / 2) as f64 * PANEL_HEIGHT;
+        let series: Vec<Vec<(f64, f64)>> =
+            algos.iter().map(|a| mean_by(&rows[a], measure)).collect();
+        panel(&mut out, x0, y0, measure.name(), &series);
+    }
+    for (i, a) in algos.iter().enumerate() {
+        let (x, y) = (2.0 * PANEL_WIDTH + 10.0, MARGIN + 16.0 * i as f64);
+        let (color, dash) = style(i);
+        let _ = writeln!(
+            out,
+            r#"<line x1="{x}" y1="{}" x2="{}" y2="{}" stroke="{color}" stroke-width="2" stroke-dasharray="{dash}"/><text x="{}" y="{}">{}</text>"#,
+            y - 4.0,
+            x + 18.0,
+            y - 4.0,
+            x + 24.0,
+            y,
+            escape(a)
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Mean comparisons at each value of `measure`, by increasing value.
+fn mean_by(rows: &[&Record], measure: Measure) -> Vec<(f64, f64)> {
+    // keyed by the bits of the value, which are ordered like it for
+    // non-negative floats
+    let mut sums: BTreeMap<u64, (f64, usize)> = BTreeMap::new();
+    for r in rows {
+        let sum = sums.entry(measure.of(r).to_bits()).or_default();
+        sum.0 += r.cmps as f64;
+        sum.1 += 1;
+    }
+    sums.into_iter()
+        .map(|(x, (total, count))| (f64::from_bits(x), total / count as f64))
+        .collect()
+}
+
+fn panel(out: &mut String, x0: f64, y0: f64, name: &str, series: &[Vec<(f64, f64)>]) {
+    let scale_x = |v: f64| (1.0 + v).log10();
+    let points = series.iter().flatten();
+    let x_max = points
+        .clone()
+        .map(|p| scale_x(p.0))
+        .fold(1.0, f64::max)
+        .ceil();
+    let y_max = nice(points.map(|p| p.1).fold(1.0, f64::max));
+
+    let (left, top) = (x0 + MARGIN, y0 + MARGIN / 2.0);
+    let (w, h) = (PANEL_WIDTH - 1.5 * MARGIN, PANEL_HEIGHT - 1.5 * MARGIN);
+    let px = |v: f64| left + scale_x(v) / x_max * w;
+    let py = |v: f64| top + h - v / y_max * h;
+
+    let _ = writeln!(
+        out,
+        r##"<rect x="{left}" y="{top}" width="{w}" height="{h}" fill="none" stroke="#888"/>"##
+    );
+    // x ticks at 0 and the powers of ten
+    for k in 0..=x_max as i32 {
+        let v = if k == 0 { 0.0 } else { 10f64.powi(k) - 1.0 };
+        let label = if k == 0 {
+            "0".to_string()
+        } else {
+            format!("1e{k}")
+        };
+        let x = px(v);
+        let _ = writeln!(
+            out,
+            r##"<line x1="{x}" y1="{top}" x2="{x}" y2="{}" stroke="#eee"/><text x="{x}" y="{}" text-anchor="middle">{label}</text>"##,
+            top + h,
+            top + h + 14.0
+        );
+    }
+    for k in 0..=4 {
+        let v = y_max * k as f64 / 4.0;
+        let y = py(v);
+        let _ = writeln!(
+            out,
+            r##"<line x1="{left}" y1="{y}" x2="{}" y2="{y}" stroke="#eee"/><text x="{}" y="{}" text-anchor="end">{v}</text>"##,
+            left + w,
+            left - 4.0,
+            y + 4.0
+        );
+    }
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+        left + w / 2.0,
+        top + h + 30.0,
+        escape(name)
+    );
+    let _ = writeln!(
+        out,
+        r#"<text transform="translate({},{}) rotate(-90)" text-anchor="middle">comparisons</text>"#,
+        x0 + 14.0,
+        top + h / 2.0
+    );
+
+    for (i, line) in series.iter().enumerate() {
+        let (color, dash) = style(i);
+        let path: Vec<String> = line
+            .iter()
+            .map(|&(x, y)| format!("{:.1},{:.1}", px(x), py(y)))
+            .collect();
+        let _ = writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{color}" stroke-dasharray="{dash}"/>"#,
+            path.join(" ")
+        );
+        for p in path {
+            let (x, y) = p.split_once(',').expect("formatted as x,y");
+            let _ = writeln!(out, r#"<circle cx="{x}" cy="{y}" r="2" fill="{color}"/>"#);
+        }
+    }
+}
+
+/// The smallest 1, 2 or 5 times a power of ten that is at least `v`.
+fn nice(v: f64) -> f64 {
+    let step = 10f64.powf(v.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * step)
+        .find(|&s| s >= v)
+        .unwrap_or(10.0 * step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Dist;
+
+    #[test]
+    fn test_plot_tells_series_apart() {
+        let record = |algo: String, runs| Record {
+            algo,
+            dist: Dist::Random,
+            n: 10,
+            disorder: 0.0,
+            inversions: 0,
+            runs,
+            rem: 0,
+            osc: 0,
+            trial: 0,
+            seed: 0,
+            threads: None,
+            cmps: 9,
+            swaps: None,
+            moves: None,
+            nanos: 0,
+            speedup: None,
+        };
+        let names: Vec<String> = (0..24)
+            .map(|i| format!("algo{i}"))
+            .chain(["a<b & \"c\"".to_string()])
+            .collect();
+        let records: Vec<_> = names.iter().map(|a| record(a.clone(), 1)).collect();
+        let svg = svg(&records);
+
+        // one legend line and one polyline per panel for each series
+        let mut styles: Vec<(&str, &str)> = svg
+            .lines()
+            .filter(|l| l.starts_with("<line") && l.contains("stroke-width"))
+            .map(|l| {
+                let attr = |name: &str| {
+                    let start = l.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+                    &l[start..start + l[start..].find('"').unwrap()]
+                };
+                (attr("stroke"), attr("stroke-dasharray"))
+            })
+            .collect();
+        assert_eq!(styles.len(), names.len());
+        styles.sort();
+        styles.dedup();
+        assert_eq!(styles.len(), names.len());
+        assert_eq!(svg.matches("<polyline").count(), 4 * names.len());
+
+        assert!(svg.contains(">a&lt;b &amp; &quot;c&quot;</text>"));
+        assert!(!svg.contains("a<b"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Measures of presortedness: how far a sequence is from sorted. Each is 0
+//! for sorted input, and adaptive sorts run faster the smaller one is.
+
+use serde::{Deserialize, Serialize};
+
+/// Every measure of one sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disorder {
+    pub inversions: u64,
+    pub runs: usize,
+    pub rem: usize,
+    pub osc: u64,
+}
+
+pub fn measure<T>(vs: &[T]) -> Disorder
+where
+    T: Ord,
+{
+    Disorder {
+        inversions: inversions(vs),
+        runs: runs(vs),
+        rem: rem(vs),
+        osc: osc(vs),
+    }
+}
+
+/// Inv: pairs `i < j` with `vs[i] > vs[j]`, counted while merge sorting
+/// the positions.
+pub fn inversions<T>(vs: &[T]) -> u64
+where
+    T: Ord,
+{
+    fn count<T: Ord>(vs: &[T], idx: &mut [usize], buf: &mut Vec<usize>) -> u64 {
+        if idx.len() < 2 {
+            return 0;
+        }
+        let mid = idx.len() / 2;
+        let mut inv = count(vs, &mut idx[..mid], buf) + count(vs, &mut idx[mid..], buf);
+
+        buf.clear();
+        let (left, right) = idx.split_at(mid);
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            if vs[right[j]] < vs[left[i]] {
+                // it jumps every element left in `left`
+                inv += (left.len() - i) as u64;
+                buf.push(right[j]);
+                j += 1;
+            } else {
+                buf.push(left[i]);
+                i += 1;
+            }
+        }
+        buf.extend_from_slice(&left[i..]);
+        buf.extend_from_slice(&right[j..]);
+        idx.copy_from_slice(buf);
+        inv
+    }
+
+    let mut idx: Vec<usize> = (0..vs.len()).collect();
+    count(vs, &mut idx, &mut Vec::with_capacity(vs.len()))
+}
+
+/// Runs: descents `vs[i] > vs[i + 1]`, one less than the number of
+/// non-decreasing runs.
+pub fn runs<T>(vs: &[T]) -> usize
+where
+    T: Ord,
+{
+    vs.windows(2).filter(|w| w[1] < w[0]).count()
+}
+
+/// Rem: fewest elements to remove to leave the rest sorted, that is `n`
+/// minus the longest non-decreasing subsequence.
+pub fn rem<T>(vs: &[T]) -> usize
+where
+    T: Ord,
+{
+    // `tails[k]` is the smallest last element of such a subsequence of length `k + 1`
+    let mut tails: Vec<&T> = Vec::new();
+    for v in vs {
+        let at = tails.partition_point(|t| *t <= v);
+        if at == tails.len() {
+            tails.push(v);
+        } else {
+            tails[at] = v;
+        }
+    }
+    vs.len() - tails.len()
+}
+
+/// Osc: for every element, the adjacent pairs it lies strictly between,
+/// summed. It is small when the sequence only wobbles locally.
+pub fn osc<T>(vs: &[T]) -> u64
+where
+    T: Ord,
+{
+    // the pairs as open intervals (lo, hi); equal neighbours make none
+    let (mut lows, mut highs): (Vec<&T>, Vec<&T>) = vs
+        .windows(2)
+        .filter(|w| w[0] != w[1])
+        .map(|w| (Ord::min(&w[0], &w[1]), Ord::max(&w[0], &w[1])))
+        .unzip();
+    lows.sort();
+    highs.sort();
+    // `lo < hi <= v` implies `lo < v`, so the intervals containing `v`
+    // are those starting below it less those already over
+    vs.iter()
+        .map(|v| {
+            let started = lows.partition_point(|lo| *lo < v);
+            let ended = highs.partition_point(|hi| *hi <= v);
+            (started - ended) as u64
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presorted_measures() {
+        use rand::Rng;
+
+        assert_eq!(measure::<u8>(&[]), Disorder::default());
+        assert_eq!(measure(&[1, 2, 2, 3]), Disorder::default());
+        assert_eq!(
+            measure(&[4, 3, 2, 1]),
+            Disorder {
+                inversions: 6,
+                runs: 3,
+                rem: 3,
+                // neighbours in a descending run have nothing between them
+                osc: 0,
+            }
+        );
+        // 2 lies between 1 and 4, 3 between 1 and 4 and between 4 and 2
+        assert_eq!(osc(&[1, 4, 2, 3]), 3);
+        assert_eq!(rem(&[1, 9, 2, 3, 4]), 1);
+        assert_eq!(runs(&[1, 9, 2, 3, 0]), 2);
+
+        // against the definitions, on small inputs
+        let mut rand = rand::thread_rng();
+        for _ in 0..200 {
+            let n = rand.gen_range(0..10);
+            let xs: Vec<u8> = (0..n).map(|_| rand.gen_range(0..6)).collect();
+            let pairs = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j)));
+            let inv = pairs.filter(|&(i, j)| xs[i] > xs[j]).count() as u64;
+            assert_eq!(inversions(&xs), inv, "{xs:?}");
+
+            // the longest non-decreasing subsequence, over every subset
+            let longest = (0u32..1 << n)
+                .filter(|set| {
+                    let kept: Vec<u8> = (0..n)
+                        .filter(|i| set >> i & 1 == 1)
+                        .map(|i| xs[i])
+                        .collect();
+                    kept.windows(2).all(|w| w[0] <= w[1])
+                })
+                .map(u32::count_ones)
+                .max()
+                .unwrap_or(0) as usize;
+            assert_eq!(rem(&xs), n - longest, "{xs:?}");
+
+            let crossings = xs
+                .iter()
+                .map(|&v| {
+                    xs.windows(2)
+                        .filter(|w| w[0].min(w[1]) < v && v < w[0].max(w[1]))
+                        .count() as u64
+                })
+                .sum::<u64>();
+            assert_eq!(osc(&xs), crossings, "{xs:?}");
+        }
+    }
+}
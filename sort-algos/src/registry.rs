@@ -0,0 +1,590 @@
+use std::cmp::Ordering;
+
+use crate::{
+    adaptive, introsort,
+    keysort::{self, cmp_from, permute, KeySort, RadixKey},
+    mergesort,
+    parallel::{self, ParSort},
+    quicksort,
+    select::{self, introselect, median_of_medians, quickselect, Select},
+    timsort,
+    tracked::{Observer, Tracked},
+    Sort,
+};
+
+/// The sorting traits are generic over the element type and so cannot be
+/// made into objects. This is their object-safe face for one element type,
+/// letting algorithms be stored and picked by name at runtime.
+pub trait DynSort<T> {
+    /// Does the algorithm's job on `slice`, see [`Goal`], reporting to
+    /// `observer` when the algorithm is observed.
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        observer: &dyn Observer<T>,
+    );
+}
+
+/// A comparison [`Sort`].
+pub struct Comparison<A>(pub A);
+
+impl<T, A> DynSort<T> for Comparison<A>
+where
+    A: Sort,
+{
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        observer: &dyn Observer<T>,
+    ) {
+        self.0.sort_by_observed(slice, compare, &observer);
+    }
+}
+
+impl<T, D> DynSort<T> for Box<D>
+where
+    D: DynSort<T> + ?Sized,
+{
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        observer: &dyn Observer<T>,
+    ) {
+        (**self).run(slice, compare, observer);
+    }
+}
+
+/// A [`KeySort`] ordering elements by themselves as keys. That order is
+/// only kept when `compare` agrees with it on every pair of neighbours;
+/// otherwise, as for a descending comparator, the slice is left to
+/// [`timsort`], which is stable like the key sorts.
+pub struct Keyed<A>(pub A);
+
+impl<T, A> DynSort<T> for Keyed<A>
+where
+    T: RadixKey,
+    A: KeySort,
+{
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        observer: &dyn Observer<T>,
+    ) {
+        let order = self.0.order(slice);
+        let agrees = order.windows(2).all(|w| {
+            let (a, b) = (&slice[w[0]], &slice[w[1]]);
+            compare(a, b) == cmp_from(a, b, 0)
+        });
+        match agrees {
+            true => permute(&mut Tracked::new(slice, &observer), order),
+            false => timsort::Algo.sort_by_observed(slice, compare, &observer),
+        }
+    }
+}
+
+/// A [`ParSort`], which reports nothing to the observer.
+pub struct Parallel<A>(pub A);
+
+impl<T, A> DynSort<T> for Parallel<A>
+where
+    T: Send + Sync,
+    A: ParSort,
+{
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        _observer: &dyn Observer<T>,
+    ) {
+        self.0.sort_by(slice, compare);
+    }
+}
+
+/// A [`Select`] putting the median in place.
+pub struct Median<A>(pub A);
+
+impl<T, A> DynSort<T> for Median<A>
+where
+    A: Select,
+{
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        observer: &dyn Observer<T>,
+    ) {
+        if !slice.is_empty() {
+            let mid = slice.len() / 2;
+            self.0
+                .select_nth_by_observed(slice, mid, compare, &observer);
+        }
+    }
+}
+
+/// The partial sorts put the smallest `n / PARTIAL` elements in order.
+pub const PARTIAL: usize = 10;
+
+/// [`select::partial_sort_by`].
+pub struct PartialSort;
+
+impl<T> DynSort<T> for PartialSort {
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        observer: &dyn Observer<T>,
+    ) {
+        let k = slice.len() / PARTIAL;
+        select::partial_sort_by_observed(slice, k, compare, &observer);
+    }
+}
+
+/// [`select::TopK`] fed the slice as a stream; the result overwrites the
+/// front of the slice, unobserved.
+pub struct TopK;
+
+impl<T> DynSort<T> for TopK
+where
+    T: Clone,
+{
+    fn run(
+        &self,
+        slice: &mut [T],
+        compare: &(dyn Fn(&T, &T) -> Ordering + Sync),
+        _observer: &dyn Observer<T>,
+    ) {
+        let k = slice.len() / PARTIAL;
+        let mut top = select::TopK::new_by(k, compare);
+        top.extend(slice.iter().cloned());
+        slice[..k].clone_from_slice(&top.into_sorted_vec());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    Comparison,
+    Key,
+    Parallel,
+    Selection,
+}
+
+/// What an algorithm leaves in the slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// everything in order
+    Sort,
+    /// the median where sorting would put it, partitioned around it
+    Median,
+    /// the smallest `n / PARTIAL` elements in order at the front
+    Smallest,
+}
+
+/// Time in the best, average and worst case, and extra space, as
+/// functions of `n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Complexity {
+    pub best: &'static str,
+    pub average: &'static str,
+    pub worst: &'static str,
+    pub space: &'static str,
+}
+
+const fn complexity(
+    best: &'static str,
+    average: &'static str,
+    worst: &'static str,
+    space: &'static str,
+) -> Complexity {
+    Complexity {
+        best,
+        average,
+        worst,
+        space,
+    }
+}
+
+pub struct Algorithm<T> {
+    pub name: &'static str,
+    pub family: Family,
+    pub goal: Goal,
+    /// equal elements keep their order; only meaningful for `Goal::Sort`
+    pub stable: bool,
+    /// rearranges the slice itself, without a buffer or index arrays
+    pub in_place: bool,
+    /// swaps and moves reach the observer
+    pub observed: bool,
+    pub complexity: Complexity,
+    pub algo: Box<dyn DynSort<T> + Send + Sync>,
+}
+
+impl<T> Algorithm<T> {
+    /// Whether `output` is what the algorithm should make of `input`.
+    pub fn verify(&self, input: &[T], output: &[T]) -> bool
+    where
+        T: Ord + Clone,
+    {
+        let mut sorted = input.to_vec();
+        sorted.sort();
+        match self.goal {
+            Goal::Sort => output == sorted,
+            Goal::Median if input.is_empty() => output.is_empty(),
+            Goal::Median => {
+                let mid = input.len() / 2;
+                output[mid] == sorted[mid]
+                    && output[..mid].iter().all(|v| *v <= output[mid])
+                    && output[mid..].iter().all(|v| *v >= output[mid])
+            }
+            Goal::Smallest => {
+                let k = input.len() / PARTIAL;
+                output[..k] == sorted[..k]
+            }
+        }
+    }
+}
+
+fn comparison<T, A>(name: &'static str, algo: A, in_place: bool, c: Complexity) -> Algorithm<T>
+where
+    A: Sort + Send + Sync + 'static,
+{
+    Algorithm {
+        name,
+        family: Family::Comparison,
+        goal: Goal::Sort,
+        stable: A::STABLE,
+        in_place,
+        observed: true,
+        complexity: c,
+        algo: Box::new(Comparison(algo)),
+    }
+}
+
+fn keyed<T, A>(name: &'static str, algo: A, c: Complexity) -> Algorithm<T>
+where
+    T: RadixKey,
+    A: KeySort + Send + Sync + 'static,
+{
+    Algorithm {
+        name,
+        family: Family::Key,
+        goal: Goal::Sort,
+        stable: A::STABLE,
+        // the sorted order is worked out in index arrays
+        in_place: false,
+        observed: true,
+        complexity: c,
+        algo: Box::new(Keyed(algo)),
+    }
+}
+
+fn parallel<T, A>(name: &'static str, algo: A, in_place: bool, c: Complexity) -> Algorithm<T>
+where
+    T: Send + Sync,
+    A: ParSort + Send + Sync + 'static,
+{
+    Algorithm {
+        name,
+        family: Family::Parallel,
+        goal: Goal::Sort,
+        stable: A::STABLE,
+        in_place,
+        observed: false,
+        complexity: c,
+        algo: Box::new(Parallel(algo)),
+    }
+}
+
+fn selection<T, A>(name: &'static str, algo: A, c: Complexity) -> Algorithm<T>
+where
+    A: Select + Send + Sync + 'static,
+{
+    Algorithm {
+        name,
+        family: Family::Selection,
+        goal: Goal::Median,
+        stable: false,
+        in_place: true,
+        observed: true,
+        complexity: c,
+        algo: Box::new(Median(algo)),
+    }
+}
+
+/// The algorithms that order elements by `compare` alone, and so work on
+/// any element type. The `_plain` variants sort small slices with insertion
+/// sort instead of a sorting network.
+pub fn comparison_algorithms<T>() -> Vec<Algorithm<T>>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    const N_LOG_N: Complexity = complexity("n log n", "n log n", "n log n", "n");
+    vec![
+        comparison(
+            "bubble",
+            crate::bubble::Algo,
+            true,
+            complexity("n", "n^2", "n^2", "1"),
+        ),
+        comparison(
+            "insertion",
+            crate::insertion::Algo,
+            true,
+            complexity("n", "n^2", "n^2", "1"),
+        ),
+        comparison(
+            "selection",
+            crate::selection::Algo,
+            true,
+            complexity("n^2", "n^2", "n^2", "1"),
+        ),
+        // the first element is the pivot, so ordered input recurses n deep
+        comparison(
+            "quicksort",
+            quicksort::Algo,
+            true,
+            complexity("n log n", "n log n", "n^2", "n"),
+        ),
+        comparison(
+            "quicksort_plain",
+            quicksort::Plain,
+            true,
+            complexity("n log n", "n log n", "n^2", "n"),
+        ),
+        comparison(
+            "mergesort",
+            mergesort::Algo,
+            false,
+            complexity("n", "n log n", "n log n", "n"),
+        ),
+        comparison(
+            "mergesort_plain",
+            mergesort::Plain,
+            false,
+            complexity("n", "n log n", "n log n", "n"),
+        ),
+        comparison(
+            "introsort",
+            introsort::Algo,
+            true,
+            complexity("n log n", "n log n", "n log n", "log n"),
+        ),
+        comparison(
+            "introsort_plain",
+            introsort::Plain,
+            true,
+            complexity("n log n", "n log n", "n log n", "log n"),
+        ),
+        comparison(
+            "timsort",
+            timsort::Algo,
+            false,
+            complexity("n", "n log n", "n log n", "n"),
+        ),
+        // `r` is the number of runs, `Rem` the elements out of order
+        comparison(
+            "natural_merge",
+            adaptive::natural::Algo,
+            false,
+            complexity("n", "n log r", "n log n", "n"),
+        ),
+        comparison(
+            "splitsort",
+            adaptive::splitsort::Algo,
+            false,
+            complexity("n", "n log Rem", "n log n", "n"),
+        ),
+        parallel(
+            "par_merge",
+            parallel::merge::Algo::default(),
+            false,
+            N_LOG_N,
+        ),
+        parallel(
+            "par_quicksort",
+            parallel::quicksort::Algo::default(),
+            true,
+            complexity("n log n", "n log n", "n log n", "log n"),
+        ),
+        parallel(
+            "par_sample",
+            parallel::sample::Algo::default(),
+            false,
+            N_LOG_N,
+        ),
+        selection(
+            "quickselect",
+            quickselect::Algo,
+            complexity("n", "n", "n^2", "1"),
+        ),
+        selection(
+            "introselect",
+            introselect::Algo,
+            complexity("n", "n", "n", "1"),
+        ),
+        selection(
+            "median_of_medians",
+            median_of_medians::Algo,
+            complexity("n", "n", "n", "1"),
+        ),
+        Algorithm {
+            name: "partial_sort",
+            family: Family::Selection,
+            goal: Goal::Smallest,
+            stable: false,
+            in_place: true,
+            observed: true,
+            complexity: complexity("n log k", "n log k", "n log k", "1"),
+            algo: Box::new(PartialSort),
+        },
+        Algorithm {
+            name: "top_k",
+            family: Family::Selection,
+            goal: Goal::Smallest,
+            stable: false,
+            in_place: false,
+            observed: false,
+            complexity: complexity("n log k", "n log k", "n log k", "k"),
+            algo: Box::new(TopK),
+        },
+    ]
+}
+
+/// The sorts that read elements as radix keys.
+pub fn key_algorithms<T>() -> Vec<Algorithm<T>>
+where
+    T: RadixKey + Send + Sync + 'static,
+{
+    vec![
+        // `w` is the key width in bytes, `k` the span of key values
+        keyed(
+            "radix_lsd",
+            keysort::radix_lsd::Algo,
+            complexity("w n", "w n", "w n", "n"),
+        ),
+        keyed(
+            "radix_msd",
+            keysort::radix_msd::Algo,
+            complexity("n", "w n", "w n", "n"),
+        ),
+        keyed(
+            "counting",
+            keysort::counting::Algo,
+            complexity("n + k", "n + k", "n + k", "n + k"),
+        ),
+        keyed(
+            "bucket",
+            keysort::bucket::Algo,
+            complexity("n", "n", "n^2", "n"),
+        ),
+    ]
+}
+
+/// Every algorithm in the crate, by name: the key sorts after the other
+/// sorts, before the parallel ones and the selections.
+pub fn algorithms<T>() -> Vec<Algorithm<T>>
+where
+    T: RadixKey + Clone + Send + Sync + 'static,
+{
+    let mut all = comparison_algorithms();
+    let at = all
+        .iter()
+        .position(|a| a.family != Family::Comparison)
+        .unwrap_or(all.len());
+    all.splice(at..at, key_algorithms());
+    all
+}
+
+/// Names of [`algorithms`], in order.
+pub fn names() -> Vec<&'static str> {
+    algorithms::<usize>().iter().map(|a| a.name).collect()
+}
+
+pub fn find<T>(name: &str) -> Option<Algorithm<T>>
+where
+    T: RadixKey + Clone + Send + Sync + 'static,
+{
+    algorithms().into_iter().find(|a| a.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracked;
+
+    #[test]
+    fn test_registry_runs_everything() {
+        use rand::Rng;
+
+        let mut names = names();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), algorithms::<usize>().len());
+        assert!(find::<usize>("nope").is_none());
+
+        let mut rand = rand::thread_rng();
+        for algo in algorithms::<u32>() {
+            for n in [0, 1, 2, 17, 500] {
+                let xs: Vec<u32> = (0..n).map(|_| rand.gen_range(0..50)).collect();
+                let mut vs = xs.clone();
+                let counts = tracked::Counts::default();
+                algo.algo.run(&mut vs, &Ord::cmp, &counts);
+                assert!(algo.verify(&xs, &vs), "{} on {xs:?}", algo.name);
+                if !algo.observed {
+                    assert_eq!((counts.swaps(), counts.moves()), (0, 0));
+                }
+            }
+            assert!(algo.goal == Goal::Sort || algo.family == Family::Selection);
+        }
+
+        // also usable for element types other than numbers
+        let mut words = vec![b"pear".to_vec(), b"fig".to_vec(), b"apple".to_vec()];
+        find("radix_msd")
+            .unwrap()
+            .algo
+            .run(&mut words, &Ord::cmp, &());
+        assert_eq!(words, [&b"apple"[..], b"fig", b"pear"]);
+    }
+    #[test]
+    fn test_registry_follows_compare() {
+        use rand::Rng;
+
+        let mut rand = rand::thread_rng();
+        let xs: Vec<u32> = (0..500).map(|_| rand.gen_range(0..50)).collect();
+        let sorts = || {
+            algorithms::<u32>()
+                .into_iter()
+                .filter(|a| a.goal == Goal::Sort)
+        };
+        for algo in sorts() {
+            let mut vs = xs.clone();
+            algo.algo.run(&mut vs, &|a, b| b.cmp(a), &());
+            assert!(vs.windows(2).all(|w| w[0] >= w[1]), "{}", algo.name);
+        }
+
+        // a comparator coarser than the keys, which stable sorts must
+        // respect by keeping the input order among its equal elements
+        let tens = |a: &u32, b: &u32| (a / 10).cmp(&(b / 10));
+        let mut expected = xs.clone();
+        expected.sort_by(tens);
+        for algo in sorts().filter(|a| a.stable) {
+            let mut vs = xs.clone();
+            algo.algo.run(&mut vs, &tens, &());
+            assert_eq!(vs, expected, "{}", algo.name);
+        }
+
+        // the comparison algorithms take any element type
+        let pairs = vec![
+            ("b".to_string(), 2),
+            ("a".to_string(), 9),
+            ("b".to_string(), 1),
+        ];
+        for algo in comparison_algorithms::<(String, u32)>() {
+            let mut vs = pairs.clone();
+            algo.algo.run(&mut vs, &Ord::cmp, &());
+            assert!(algo.verify(&pairs, &vs), "{}", algo.name);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+use std::cmp::Ordering;
+
+use crate::{
+    network,
+    quicksort::partition,
+    tracked::{Observer, Tracked},
+};
+
+/// Selection: put one element where a full sort would, without sorting.
+pub trait Select {
+    /// Reorders `slice` so `slice[n]` is the element a sort would put there,
+    /// with nothing greater before it and nothing less after it, reporting
+    /// to `observer`. Panics when `n` is out of bounds.
+    fn select_nth_by_observed<T, F, O>(&self, slice: &mut [T], n: usize, compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>;
+
+    fn select_nth_by<T, F>(&self, slice: &mut [T], n: usize, compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.select_nth_by_observed(slice, n, compare, &());
+    }
+
+    fn select_nth<T>(&self, slice: &mut [T], n: usize)
+    where
+        T: Ord,
+    {
+        self.select_nth_by(slice, n, T::cmp);
+    }
+}
+
+pub mod quickselect {
+    use super::*;
+
+    /// Quickselect, pivoting on the middle element. Linear on average,
+    /// quadratic on inputs built against it.
+    pub struct Algo;
+
+    impl Select for Algo {
+        fn select_nth_by_observed<T, F, O>(
+            &self,
+            vs: &mut [T],
+            n: usize,
+            mut compare: F,
+            observer: &O,
+        ) where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            select(&mut Tracked::new(vs, observer), n, &mut compare, usize::MAX);
+        }
+    }
+}
+
+pub mod introselect {
+    use super::*;
+
+    /// Quickselect that switches to median-of-medians pivots once it has
+    /// partitioned `2 log2(n)` times, which bounds it to linear time.
+    pub struct Algo;
+
+    impl Select for Algo {
+        fn select_nth_by_observed<T, F, O>(
+            &self,
+            vs: &mut [T],
+            n: usize,
+            mut compare: F,
+            observer: &O,
+        ) where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            let limit = 2 * vs.len().max(1).ilog2() as usize;
+            select(&mut Tracked::new(vs, observer), n, &mut compare, limit);
+        }
+    }
+}
+
+pub mod median_of_medians {
+    use super::*;
+
+    /// Selection with the median of the medians of groups of five as every
+    /// pivot: linear in the worst case, slower than quickselect in practice.
+    pub struct Algo;
+
+    impl Select for Algo {
+        fn select_nth_by_observed<T, F, O>(
+            &self,
+            vs: &mut [T],
+            n: usize,
+            mut compare: F,
+            observer: &O,
+        ) where
+            F: FnMut(&T, &T) -> Ordering,
+            O: Observer<T>,
+        {
+            select(&mut Tracked::new(vs, observer), n, &mut compare, 0);
+        }
+    }
+}
+
+/// Narrows down on `n` by partitioning. The middle element is the pivot
+/// for the first `limit` rounds, the median of medians after that.
+fn select<T, F, O>(vs: &mut Tracked<T, O>, n: usize, compare: &mut F, mut limit: usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    assert!(n < vs.len(), "index {n} out of bounds for {}", vs.len());
+    // [ <= vs[n] | lo..hi not partitioned | >= vs[n] ]
+    let (mut lo, mut hi) = (0, vs.len());
+    while hi - lo > 1 {
+        let mut part = vs.slice(lo..hi);
+        let pivot = if limit == 0 {
+            median_of_medians(&mut part, compare)
+        } else {
+            limit -= 1;
+            part.len() / 2
+        };
+        if pivot != 0 {
+            part.swap(0, pivot);
+        }
+        let at = lo + partition(&mut part, compare);
+        match n.cmp(&at) {
+            Ordering::Less => hi = at,
+            Ordering::Equal => return,
+            Ordering::Greater => lo = at + 1,
+        }
+    }
+}
+
+/// Returns the position of the median of the medians of groups of five,
+/// gathering the group medians at the front of `vs` along the way.
+fn median_of_medians<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let groups = vs.len().div_ceil(5);
+    for g in 0..groups {
+        let start = g * 5;
+        let end = (start + 5).min(vs.len());
+        network::insertion_sort(&mut vs.slice(start..end), compare);
+        let median = start + (end - start - 1) / 2;
+        if median != g {
+            vs.swap(g, median);
+        }
+    }
+    if groups > 1 {
+        select(&mut vs.slice(..groups), groups / 2, compare, 0);
+    }
+    groups / 2
+}
+
+/// Rearranges `slice` so `slice[..k]` holds its `k` smallest elements in
+/// order, using a max-heap of the best `k` seen so far. The order of the
+/// rest is unspecified.
+pub fn partial_sort<T>(slice: &mut [T], k: usize)
+where
+    T: Ord,
+{
+    partial_sort_by(slice, k, T::cmp);
+}
+
+pub fn partial_sort_by<T, F>(slice: &mut [T], k: usize, compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    partial_sort_by_observed(slice, k, compare, &());
+}
+
+pub fn partial_sort_by_observed<T, F, O>(slice: &mut [T], k: usize, mut compare: F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let mut vs = Tracked::new(slice, observer);
+    let k = k.min(vs.len());
+    if k == 0 {
+        return;
+    }
+    for i in (0..k / 2).rev() {
+        sift_down(&mut vs, i, k, &mut compare);
+    }
+    // [ heap of the k smallest seen | not seen ]
+    for i in k..vs.len() {
+        if vs.compare(i, 0, &mut compare) == Ordering::Less {
+            vs.swap(0, i);
+            sift_down(&mut vs, 0, k, &mut compare);
+        }
+    }
+    sort_heap(&mut vs, k, &mut compare);
+}
+
+/// The `k` smallest elements of a stream, kept in a max-heap so each new
+/// element costs `O(log k)` and memory stays at `k`.
+pub struct TopK<T, F> {
+    k: usize,
+    heap: Vec<T>,
+    compare: F,
+}
+
+impl<T> TopK<T, fn(&T, &T) -> Ordering>
+where
+    T: Ord,
+{
+    pub fn new(k: usize) -> Self {
+        TopK::new_by(k, T::cmp)
+    }
+}
+
+impl<T, F> TopK<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub fn new_by(k: usize, compare: F) -> Self {
+        TopK {
+            k,
+            heap: Vec::with_capacity(k),
+            compare,
+        }
+    }
+
+    pub fn push(&mut self, v: T) {
+        if self.heap.len() < self.k {
+            self.heap.push(v);
+            let last = self.heap.len() - 1;
+            sift_up(
+                &mut Tracked::new(&mut self.heap, &()),
+                last,
+                &mut self.compare,
+            );
+        } else if self.k > 0 && (self.compare)(&v, &self.heap[0]) == Ordering::Less {
+            self.heap[0] = v;
+            let k = self.k;
+            sift_down(
+                &mut Tracked::new(&mut self.heap, &()),
+                0,
+                k,
+                &mut self.compare,
+            );
+        }
+    }
+
+    /// The smallest elements pushed so far, in order.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let len = self.heap.len();
+        sort_heap(
+            &mut Tracked::new(&mut self.heap, &()),
+            len,
+            &mut self.compare,
+        );
+        self.heap
+    }
+}
+
+impl<T, F> Extend<T> for TopK<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for v in iter {
+            self.push(v);
+        }
+    }
+}
+
+/// The `k` smallest items of `iter`, in order.
+pub fn top_k<T, I>(iter: I, k: usize) -> Vec<T>
+where
+    T: Ord,
+    I: IntoIterator<Item = T>,
+{
+    let mut top = TopK::new(k);
+    top.extend(iter);
+    top.into_sorted_vec()
+}
+
+/// Heapsort, for when partitioning keeps going badly.
+pub(crate) fn heapsort<T, F, O>(vs: &mut Tracked<T, O>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let len = vs.len();
+    for i in (0..len / 2).rev() {
+        sift_down(vs, i, len, compare);
+    }
+    sort_heap(vs, len, compare);
+}
+
+/// Restores the max-heap `vs[..len]` below `root`.
+fn sift_down<T, F, O>(vs: &mut Tracked<T, O>, mut root: usize, len: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= len {
+            return;
+        }
+        if child + 1 < len && vs.compare(child, child + 1, compare) == Ordering::Less {
+            child += 1;
+        }
+        if vs.compare(root, child, compare) != Ordering::Less {
+            return;
+        }
+        vs.swap(root, child);
+        root = child;
+    }
+}
+
+/// Restores the max-heap after `vs[i]` was added at the bottom.
+fn sift_up<T, F, O>(vs: &mut Tracked<T, O>, mut i: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if vs.compare(parent, i, compare) != Ordering::Less {
+            return;
+        }
+        vs.swap(parent, i);
+        i = parent;
+    }
+}
+
+/// Sorts the max-heap `vs[..len]` in place.
+fn sort_heap<T, F, O>(vs: &mut Tracked<T, O>, len: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    for end in (1..len).rev() {
+        vs.swap(0, end);
+        sift_down(vs, 0, end, compare);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let xs = [9, 1, 8, 2, 7, 3, 6, 4, 5, 0];
+        for n in 0..xs.len() {
+            let mut vs = xs;
+            quickselect::Algo.select_nth(&mut vs, n);
+            assert_eq!(vs[n], n);
+            let mut vs = xs;
+            introselect::Algo.select_nth_by(&mut vs, n, |a, b| b.cmp(a));
+            assert_eq!(vs[n], 9 - n);
+            let mut vs = xs;
+            median_of_medians::Algo.select_nth(&mut vs, n);
+            assert_eq!(vs[n], n);
+        }
+
+        // median-of-medians stays linear whatever the input
+        use rand::Rng;
+        let n = 1 << 12;
+        let mut rand = rand::thread_rng();
+        for mut vs in [
+            (0..n).collect::<Vec<usize>>(),
+            (0..n).map(|_| rand.gen_range(0..n)).collect(),
+        ] {
+            let mut cmps = 0;
+            median_of_medians::Algo.select_nth_by(&mut vs, n / 2, |a, b| {
+                cmps += 1;
+                a.cmp(b)
+            });
+            assert!(cmps < 40 * n, "{cmps}");
+        }
+
+        let mut vs = [5, 3, 9, 1, 1, 7];
+        partial_sort_by(&mut vs, 3, |a, b| b.cmp(a));
+        assert_eq!(vs[..3], [9, 7, 5]);
+
+        let mut top = TopK::new_by(2, |a: &&str, b: &&str| a.len().cmp(&b.len()));
+        top.extend(["ccc", "a", "dddd", "bb"]);
+        assert_eq!(top.into_sorted_vec(), ["a", "bb"]);
+        assert!(top_k(0..10, 0).is_empty());
+    }
+}
@@ -0,0 +1,535 @@
+use std::{cell::Cell, ptr};
+
+use super::*;
+
+pub struct Algo;
+
+impl Sort for Algo {
+    const STABLE: bool = true;
+
+    fn sort_by_observed<T, F, O>(&self, vs: &mut [T], mut compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        // merges compare elements in the buffer, so comparisons are reported
+        // from the comparator rather than through `Tracked::compare`
+        let locate = Locate::new(vs);
+        let mut vs = Tracked::new(vs, observer);
+        timsort(&mut vs, &locate, &mut |a: &T, b: &T| {
+            observer.compare(locate.position(a), locate.position(b));
+            compare(a, b) == Ordering::Less
+        });
+    }
+}
+
+/// Maps elements handed to the comparator back to their position in the
+/// slice being sorted, including elements sitting in the merge buffer.
+struct Locate<T> {
+    origin: *const T,
+    len: usize,
+    // start and length of the buffered run, and the position it was taken from
+    buf: Cell<(*const T, usize, usize)>,
+}
+
+impl<T> Locate<T> {
+    fn new(vs: &[T]) -> Self {
+        Locate {
+            origin: vs.as_ptr(),
+            len: vs.len(),
+            buf: Cell::new((ptr::null(), 0, 0)),
+        }
+    }
+
+    fn position(&self, x: *const T) -> usize {
+        let size = std::mem::size_of::<T>().max(1);
+        let offset = |from: *const T| (x as usize).wrapping_sub(from as usize) / size;
+        let i = offset(self.origin);
+        if i < self.len {
+            return i;
+        }
+        let (buf, len, base) = self.buf.get();
+        debug_assert!(offset(buf) < len);
+        base + offset(buf)
+    }
+}
+
+// runs shorter than this are sorted with binary insertion alone
+const MIN_MERGE: usize = 64;
+// initial threshold before a merge switches to galloping mode
+const MIN_GALLOP: usize = 7;
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    base: usize,
+    len: usize,
+}
+
+fn timsort<T, F, O>(vs: &mut Tracked<T, O>, locate: &Locate<T>, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer<T>,
+{
+    let n = vs.len();
+    if n < 2 {
+        return;
+    }
+
+    if n < MIN_MERGE {
+        let run = count_run_and_make_ascending(vs, is_less);
+        binary_insertion_sort(vs, run, is_less);
+        return;
+    }
+
+    let mut ts = TimSort {
+        buf: Vec::with_capacity(n / 2),
+        runs: Vec::new(),
+        min_gallop: MIN_GALLOP,
+        locate,
+    };
+
+    // [ merged runs | not visited ]
+    let min_run = min_run(n);
+    let mut base = 0;
+    while base < n {
+        let mut rest = vs.slice(base..);
+        let mut len = count_run_and_make_ascending(&mut rest, is_less);
+        if len < min_run {
+            let force = min_run.min(rest.len());
+            binary_insertion_sort(&mut rest.slice(..force), len, is_less);
+            len = force;
+        }
+
+        ts.runs.push(Run { base, len });
+        ts.merge_collapse(vs, is_less);
+        base += len;
+    }
+
+    ts.merge_force_collapse(vs, is_less);
+    debug_assert_eq!(ts.runs.len(), 1);
+}
+
+/// Picks a run length in `[MIN_MERGE / 2, MIN_MERGE]` such that `n / min_run`
+/// is a power of two, or slightly less than one, which keeps merges balanced.
+fn min_run(mut n: usize) -> usize {
+    let mut r = 0;
+    while n >= MIN_MERGE {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+/// Returns the length of the run starting at `vs[0]`, reversing it in place
+/// when it is strictly descending. Strictness keeps the reversal stable.
+fn count_run_and_make_ascending<T, F, O>(vs: &mut Tracked<T, O>, is_less: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer<T>,
+{
+    if vs.len() < 2 {
+        return vs.len();
+    }
+
+    let mut end = 2;
+    if is_less(&vs[1], &vs[0]) {
+        while end < vs.len() && is_less(&vs[end], &vs[end - 1]) {
+            end += 1;
+        }
+        vs.reverse(0..end);
+    } else {
+        while end < vs.len() && !is_less(&vs[end], &vs[end - 1]) {
+            end += 1;
+        }
+    }
+    end
+}
+
+/// Sorts `vs` assuming `vs[..sorted]` is already sorted.
+fn binary_insertion_sort<T, F, O>(vs: &mut Tracked<T, O>, sorted: usize, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer<T>,
+{
+    // [ sort | not sort ]
+    for unsort in sorted.max(1)..vs.len() {
+        let (sort, rest) = vs.split_at(unsort);
+        // insert after any equal elements to stay stable
+        let pos = sort.partition_point(|x| !is_less(&rest[0], x));
+        vs.rotate_right(pos..unsort + 1, 1);
+    }
+}
+
+/// Number of leading elements of `run` for which `pred` holds, probing
+/// `run[0]`, `run[2]`, `run[6]`, `run[14]`, ... before a binary search.
+/// `pred` must hold for a prefix of `run` and fail for the rest.
+fn gallop<T>(run: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+    let (mut lo, mut hi) = (0, run.len());
+    let mut ofs = 1;
+    while ofs <= run.len() {
+        if pred(&run[ofs - 1]) {
+            lo = ofs;
+            ofs = ofs * 2 + 1;
+        } else {
+            hi = ofs - 1;
+            break;
+        }
+    }
+    lo + run[lo..hi].partition_point(pred)
+}
+
+/// Same as [`gallop`], probing from the end of `run` instead.
+fn gallop_rev<T>(run: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+    let (mut lo, mut hi) = (0, run.len());
+    let mut ofs = 1;
+    while ofs <= run.len() {
+        if pred(&run[run.len() - ofs]) {
+            lo = run.len() - ofs + 1;
+            break;
+        } else {
+            hi = run.len() - ofs;
+            ofs = ofs * 2 + 1;
+        }
+    }
+    lo + run[lo..hi].partition_point(pred)
+}
+
+struct TimSort<'a, T> {
+    // scratch space; its length stays 0 so it never drops anything
+    buf: Vec<T>,
+    runs: Vec<Run>,
+    min_gallop: usize,
+    locate: &'a Locate<T>,
+}
+
+impl<T> TimSort<'_, T> {
+    /// Merges runs until the stack satisfies, for the top runs `X`, `Y`, `Z`
+    /// (`Z` on top), both `len(X) > len(Y) + len(Z)` and `len(Y) > len(Z)`.
+    /// The check reaches one run deeper than the original paper, which is
+    /// needed for the invariant to hold over the whole stack.
+    fn merge_collapse<F, O>(&mut self, vs: &mut Tracked<T, O>, is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+        O: Observer<T>,
+    {
+        while self.runs.len() > 1 {
+            let r = &self.runs;
+            let mut n = r.len() - 2;
+            if (n > 0 && r[n - 1].len <= r[n].len + r[n + 1].len)
+                || (n > 1 && r[n - 2].len <= r[n - 1].len + r[n].len)
+            {
+                if r[n - 1].len < r[n + 1].len {
+                    n -= 1;
+                }
+            } else if r[n].len > r[n + 1].len {
+                break;
+            }
+            self.merge_at(n, vs, is_less);
+        }
+    }
+
+    fn merge_force_collapse<F, O>(&mut self, vs: &mut Tracked<T, O>, is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+        O: Observer<T>,
+    {
+        while self.runs.len() > 1 {
+            let r = &self.runs;
+            let mut n = r.len() - 2;
+            if n > 0 && r[n - 1].len < r[n + 1].len {
+                n -= 1;
+            }
+            self.merge_at(n, vs, is_less);
+        }
+    }
+
+    /// Merges the adjacent runs `runs[i]` and `runs[i + 1]`.
+    fn merge_at<F, O>(&mut self, i: usize, vs: &mut Tracked<T, O>, is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+        O: Observer<T>,
+    {
+        let Run { base, len: len1 } = self.runs[i];
+        let len2 = self.runs[i + 1].len;
+        self.runs[i].len = len1 + len2;
+        self.runs.remove(i + 1);
+
+        let mut vs = vs.slice(base..base + len1 + len2);
+
+        // elements of run1 not greater than run2[0] are already in place
+        let skip = gallop(&vs[..len1], |x| !is_less(&vs[len1], x));
+        let mut vs = vs.slice(skip..);
+        let len1 = len1 - skip;
+        if len1 == 0 {
+            return;
+        }
+
+        // elements of run2 not less than the last of run1 are already in place
+        let len2 = gallop_rev(&vs[len1..], |x| is_less(x, &vs[len1 - 1]));
+        let mut vs = vs.slice(..len1 + len2);
+        if len2 == 0 {
+            return;
+        }
+
+        let base = vs.base();
+        if len1 <= len2 {
+            self.merge_lo(vs.untracked(), base, len1, is_less);
+        } else {
+            self.merge_hi(vs.untracked(), base, len1, is_less);
+        }
+        self.locate.buf.set((ptr::null(), 0, 0));
+        // the shorter run went through the buffer and every slot was rewritten
+        vs.buffered(len1.min(len2));
+        vs.written(0..len1 + len2);
+    }
+
+    /// Merges `vs[..mid]` and `vs[mid..]` front to back, buffering the left run.
+    /// `base` is the position of `vs` in the whole slice.
+    fn merge_lo<F>(&mut self, vs: &mut [T], base: usize, mid: usize, is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let len = vs.len();
+        self.buf.reserve(mid);
+        let v = vs.as_mut_ptr();
+        let buf = self.buf.as_mut_ptr();
+        self.locate.buf.set((buf, mid, base));
+
+        // SAFETY: `buf` has room for `mid` elements. From here on every slot
+        // of `vs` is either initialized or covered by `hole`, which copies the
+        // unmerged part of `buf` back into the gap if `is_less` panics.
+        unsafe {
+            ptr::copy_nonoverlapping(v, buf, mid);
+            let mut hole = MergeHole {
+                start: buf,
+                end: buf.add(mid),
+                dest: v,
+            };
+            let mut right = v.add(mid);
+            let right_end = v.add(len);
+
+            'outer: loop {
+                let (mut count_l, mut count_r) = (0, 0);
+
+                // one pair at a time until a side keeps winning
+                while count_l < self.min_gallop && count_r < self.min_gallop {
+                    if is_less(&*right, &*hole.start) {
+                        ptr::copy_nonoverlapping(right, hole.dest, 1);
+                        right = right.add(1);
+                        count_r += 1;
+                        count_l = 0;
+                    } else {
+                        ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                        hole.start = hole.start.add(1);
+                        count_l += 1;
+                        count_r = 0;
+                    }
+                    hole.dest = hole.dest.add(1);
+                    if hole.start == hole.end || right == right_end {
+                        break 'outer;
+                    }
+                }
+
+                // galloping: copy whole stretches found by exponential search
+                loop {
+                    let left = hole.slice();
+                    count_l = gallop(left, |x| !is_less(&*right, x));
+                    ptr::copy_nonoverlapping(hole.start, hole.dest, count_l);
+                    hole.start = hole.start.add(count_l);
+                    hole.dest = hole.dest.add(count_l);
+                    if hole.start == hole.end {
+                        break 'outer;
+                    }
+
+                    ptr::copy_nonoverlapping(right, hole.dest, 1);
+                    right = right.add(1);
+                    hole.dest = hole.dest.add(1);
+                    if right == right_end {
+                        break 'outer;
+                    }
+
+                    let rest =
+                        std::slice::from_raw_parts(right, right_end.offset_from(right) as usize);
+                    count_r = gallop(rest, |x| is_less(x, &*hole.start));
+                    ptr::copy(right, hole.dest, count_r);
+                    right = right.add(count_r);
+                    hole.dest = hole.dest.add(count_r);
+                    if right == right_end {
+                        break 'outer;
+                    }
+
+                    ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                    hole.start = hole.start.add(1);
+                    hole.dest = hole.dest.add(1);
+                    if hole.start == hole.end {
+                        break 'outer;
+                    }
+
+                    if self.min_gallop > 1 {
+                        self.min_gallop -= 1;
+                    }
+                    if count_l < MIN_GALLOP && count_r < MIN_GALLOP {
+                        break;
+                    }
+                }
+                // penalize leaving galloping mode
+                self.min_gallop += 2;
+            }
+            // dropping `hole` moves what is left of the left run into place
+        }
+    }
+
+    /// Merges `vs[..mid]` and `vs[mid..]` back to front, buffering the right run.
+    fn merge_hi<F>(&mut self, vs: &mut [T], base: usize, mid: usize, is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let len = vs.len();
+        self.buf.reserve(len - mid);
+        let v = vs.as_mut_ptr();
+        let buf = self.buf.as_mut_ptr();
+        self.locate.buf.set((buf, len - mid, base + mid));
+
+        // SAFETY: see `merge_lo`; here `hole.dest` trails the unmerged left
+        // run so the gap always sits right after it.
+        unsafe {
+            ptr::copy_nonoverlapping(v.add(mid), buf, len - mid);
+            let mut hole = MergeHole {
+                start: buf,
+                end: buf.add(len - mid),
+                dest: v.add(mid),
+            };
+            // one past the last unmerged element of the left run; equals `hole.dest`
+            let mut out = v.add(len);
+
+            'outer: loop {
+                let (mut count_l, mut count_r) = (0, 0);
+
+                while count_l < self.min_gallop && count_r < self.min_gallop {
+                    out = out.sub(1);
+                    let left = hole.dest.sub(1);
+                    let right = hole.end.sub(1);
+                    if is_less(&*right, &*left) {
+                        ptr::copy_nonoverlapping(left, out, 1);
+                        hole.dest = left;
+                        count_l += 1;
+                        count_r = 0;
+                    } else {
+                        ptr::copy_nonoverlapping(right, out, 1);
+                        hole.end = right;
+                        count_r += 1;
+                        count_l = 0;
+                    }
+                    if hole.start == hole.end || hole.dest == v {
+                        break 'outer;
+                    }
+                }
+
+                loop {
+                    let left = std::slice::from_raw_parts(v, hole.dest.offset_from(v) as usize);
+                    count_l = left.len() - gallop_rev(left, |x| !is_less(&*hole.end.sub(1), x));
+                    out = out.sub(count_l);
+                    hole.dest = hole.dest.sub(count_l);
+                    ptr::copy(hole.dest, out, count_l);
+                    if hole.dest == v {
+                        break 'outer;
+                    }
+
+                    out = out.sub(1);
+                    hole.end = hole.end.sub(1);
+                    ptr::copy_nonoverlapping(hole.end, out, 1);
+                    if hole.start == hole.end {
+                        break 'outer;
+                    }
+
+                    let right = hole.slice();
+                    count_r = right.len() - gallop_rev(right, |x| is_less(x, &*hole.dest.sub(1)));
+                    out = out.sub(count_r);
+                    hole.end = hole.end.sub(count_r);
+                    ptr::copy_nonoverlapping(hole.end, out, count_r);
+                    if hole.start == hole.end {
+                        break 'outer;
+                    }
+
+                    out = out.sub(1);
+                    hole.dest = hole.dest.sub(1);
+                    ptr::copy_nonoverlapping(hole.dest, out, 1);
+                    if hole.dest == v {
+                        break 'outer;
+                    }
+
+                    if self.min_gallop > 1 {
+                        self.min_gallop -= 1;
+                    }
+                    if count_l < MIN_GALLOP && count_r < MIN_GALLOP {
+                        break;
+                    }
+                }
+                self.min_gallop += 2;
+            }
+        }
+    }
+}
+
+/// Buffered elements `start..end` that belong at `dest` in the slice being
+/// merged. Dropping it writes them back, on success and on panic alike.
+pub(crate) struct MergeHole<T> {
+    pub(crate) start: *mut T,
+    pub(crate) end: *mut T,
+    pub(crate) dest: *mut T,
+}
+
+impl<T> MergeHole<T> {
+    unsafe fn slice<'a>(&self) -> &'a [T] {
+        std::slice::from_raw_parts(self.start, self.end.offset_from(self.start) as usize)
+    }
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        // SAFETY: `start..end` is initialized and `dest` has room for it.
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{test_it, test_it_by};
+
+    #[test]
+    fn test_timsort_works() {
+        test_it(&Algo);
+        test_it_by(&Algo);
+    }
+
+    #[test]
+    fn test_timsort_merges_stably() {
+        use rand::Rng;
+        let mut rand = rand::thread_rng();
+        for n in [64, 65, 100, 1000, 5000] {
+            // few distinct keys and long runs exercise both merge directions
+            // and galloping; the index tells equal keys apart
+            let mut xs: Vec<_> = (0..n)
+                .map(|i| match i / 256 % 3 {
+                    0 => rand.gen_range(0..8),
+                    1 => i,
+                    _ => n - i,
+                })
+                .collect();
+            let mut ys = xs.clone();
+            ys.sort();
+            Algo.sort(&mut xs);
+            assert_eq!(xs, ys);
+
+            let mut xs: Vec<_> = (0..n).map(|i| (rand.gen_range(0..16), i)).collect();
+            let mut ys = xs.clone();
+            ys.sort_by_key(|&(k, _)| k);
+            Algo.sort_by_key(&mut xs, |&(k, _)| k);
+            assert_eq!(xs, ys);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    ops::{Bound, Deref, Range, RangeBounds},
+    ptr,
+};
+
+/// Receives what an algorithm does to the slice it sorts. Positions are
+/// indices into that slice. Every method defaults to doing nothing, and
+/// `()` is the observer that ignores everything. Methods take `&self` so
+/// the comparator and the slice can report to the same observer; keep any
+/// state in cells.
+pub trait Observer<T> {
+    /// The elements at `i` and `j` were compared.
+    fn compare(&self, _i: usize, _j: usize) {}
+
+    /// The elements at `i` and `j` were exchanged.
+    fn swap(&self, _i: usize, _j: usize) {}
+
+    /// `v` was moved into `i` from elsewhere.
+    fn write(&self, _i: usize, _v: &T) {}
+
+    /// `n` elements were moved out into scratch space.
+    fn buffer(&self, _n: usize) {}
+}
+
+impl<T> Observer<T> for () {}
+
+impl<T, O> Observer<T> for &O
+where
+    O: Observer<T> + ?Sized,
+{
+    fn compare(&self, i: usize, j: usize) {
+        (**self).compare(i, j);
+    }
+
+    fn swap(&self, i: usize, j: usize) {
+        (**self).swap(i, j);
+    }
+
+    fn write(&self, i: usize, v: &T) {
+        (**self).write(i, v);
+    }
+
+    fn buffer(&self, n: usize) {
+        (**self).buffer(n);
+    }
+}
+
+/// Tallies swaps and element moves. Moves are elements written into the
+/// slice or into scratch space; swaps are counted apart.
+#[derive(Debug, Default, Clone)]
+pub struct Counts {
+    swaps: Cell<usize>,
+    moves: Cell<usize>,
+}
+
+impl Counts {
+    pub fn swaps(&self) -> usize {
+        self.swaps.get()
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves.get()
+    }
+}
+
+impl<T> Observer<T> for Counts {
+    fn swap(&self, _i: usize, _j: usize) {
+        self.swaps.set(self.swaps.get() + 1);
+    }
+
+    fn write(&self, _i: usize, _v: &T) {
+        self.moves.set(self.moves.get() + 1);
+    }
+
+    fn buffer(&self, n: usize) {
+        self.moves.set(self.moves.get() + n);
+    }
+}
+
+/// One step of a sort, as seen by a [`Recorder`]. A write carries the value
+/// written so the steps can be replayed from the input alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<T> {
+    Compare(usize, usize),
+    Swap(usize, usize),
+    Write(usize, T),
+}
+
+/// Keeps every event in order.
+#[derive(Debug, Default)]
+pub struct Recorder<T> {
+    events: RefCell<Vec<Event<T>>>,
+}
+
+impl<T> Recorder<T> {
+    pub fn new() -> Self {
+        Recorder {
+            events: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn into_events(self) -> Vec<Event<T>> {
+        self.events.into_inner()
+    }
+}
+
+impl<T> Observer<T> for Recorder<T>
+where
+    T: Clone,
+{
+    fn compare(&self, i: usize, j: usize) {
+        self.events.borrow_mut().push(Event::Compare(i, j));
+    }
+
+    fn swap(&self, i: usize, j: usize) {
+        self.events.borrow_mut().push(Event::Swap(i, j));
+    }
+
+    fn write(&self, i: usize, v: &T) {
+        self.events.borrow_mut().push(Event::Write(i, v.clone()));
+    }
+}
+
+/// Applies `events` to `vs`, e.g. to check a recording against the sort.
+pub fn replay<T>(vs: &mut [T], events: &[Event<T>])
+where
+    T: Clone,
+{
+    for e in events {
+        match e {
+            Event::Compare(..) => {}
+            Event::Swap(i, j) => vs.swap(*i, *j),
+            Event::Write(i, v) => vs[*i] = v.clone(),
+        }
+    }
+}
+
+/// A mutable slice that reports every change to an [`Observer`]. Reads go
+/// through `Deref`; writes and comparisons through the methods below.
+pub struct Tracked<'a, T, O: ?Sized> {
+    vs: &'a mut [T],
+    // position of `vs[0]` in the slice the observer sees
+    base: usize,
+    observer: &'a O,
+}
+
+impl<'a, T, O> Tracked<'a, T, O>
+where
+    O: Observer<T> + ?Sized,
+{
+    pub fn new(vs: &'a mut [T], observer: &'a O) -> Self {
+        Tracked {
+            vs,
+            base: 0,
+            observer,
+        }
+    }
+
+    pub fn compare<F>(&self, i: usize, j: usize, compare: &mut F) -> Ordering
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.observer.compare(self.base + i, self.base + j);
+        compare(&self.vs[i], &self.vs[j])
+    }
+
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.vs.swap(i, j);
+        self.observer.swap(self.base + i, self.base + j);
+    }
+
+    /// Swaps `vs[i]` and `vs[j]` when `swap` holds, without branching on
+    /// it for the move itself.
+    pub fn swap_if(&mut self, i: usize, j: usize, swap: bool) {
+        assert!(i < self.vs.len() && j < self.vs.len());
+        let v = self.vs.as_mut_ptr();
+        // SAFETY: both positions are in bounds. `tmp` takes the value bound
+        // for `j` before `i` is overwritten, and `ptr::copy` allows `i == j`.
+        unsafe {
+            let (a, b) = (v.add(i), v.add(j));
+            let from_a = if swap { b } else { a };
+            let from_b = if swap { a } else { b };
+            let tmp = ptr::read(from_b);
+            ptr::copy(from_a, a, 1);
+            ptr::write(b, tmp);
+        }
+        if swap {
+            self.observer.swap(self.base + i, self.base + j);
+        }
+    }
+
+    pub fn rotate_right(&mut self, range: Range<usize>, k: usize) {
+        self.vs[range.clone()].rotate_right(k);
+        if !k.is_multiple_of(range.len().max(1)) {
+            self.written(range);
+        }
+    }
+
+    pub fn reverse(&mut self, range: Range<usize>) {
+        let (mut i, mut j) = (range.start, range.end);
+        while i + 1 < j {
+            j -= 1;
+            self.swap(i, j);
+            i += 1;
+        }
+    }
+
+    /// Reborrows part of the slice; the observer keeps seeing positions in
+    /// the whole slice.
+    pub fn slice(&mut self, range: impl RangeBounds<usize>) -> Tracked<'_, T, O> {
+        let start = match range.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s + 1,
+            Bound::Unbounded => 0,
+        };
+        Tracked {
+            vs: &mut self.vs[(range.start_bound().cloned(), range.end_bound().cloned())],
+            base: self.base + start,
+            observer: self.observer,
+        }
+    }
+
+    /// Position of this part within the whole slice.
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn observer(&self) -> &'a O {
+        self.observer
+    }
+
+    /// Mutable access that bypasses the observer; pair it with `written`
+    /// and `buffered` to report what was done.
+    pub fn untracked(&mut self) -> &mut [T] {
+        self.vs
+    }
+
+    pub fn written(&mut self, range: Range<usize>) {
+        for i in range {
+            self.observer.write(self.base + i, &self.vs[i]);
+        }
+    }
+
+    pub fn buffered(&mut self, n: usize) {
+        self.observer.buffer(n);
+    }
+}
+
+impl<T, O> Deref for Tracked<'_, T, O>
+where
+    O: ?Sized,
+{
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.vs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bubble, insertion, introsort,
+        keysort::{self, KeySort},
+        mergesort, quicksort, selection, timsort, Sort,
+    };
+
+    #[test]
+    fn test_counts_moves() {
+        // (swaps, moves)
+        fn counts<A: Sort>(go: &A, mut xs: Vec<usize>) -> (usize, usize) {
+            let counts = Counts::default();
+            go.sort_by_observed(&mut xs, Ord::cmp, &counts);
+            (counts.swaps(), counts.moves())
+        }
+
+        // nothing moves when the input is already sorted
+        let sorted: Vec<usize> = (0..200).collect();
+        assert_eq!(counts(&bubble::Algo, sorted.clone()), (0, 0));
+        assert_eq!(counts(&insertion::Algo, sorted.clone()), (0, 0));
+        assert_eq!(counts(&selection::Algo, sorted.clone()), (0, 0));
+        assert_eq!(counts(&timsort::Algo, sorted), (0, 0));
+
+        let reversed = vec![3, 2, 1];
+        assert_eq!(counts(&bubble::Algo, reversed.clone()), (3, 0));
+        assert_eq!(counts(&selection::Algo, reversed.clone()), (1, 0));
+        assert_eq!(counts(&timsort::Algo, reversed.clone()), (1, 0));
+        // 2 rotates into place, then 1 does
+        assert_eq!(counts(&insertion::Algo, reversed), (0, 5));
+
+        // timsort merges only the overlap, 51..100 with 50..99, buffering
+        // one side and rewriting both
+        let xs: Vec<usize> = (0..100).chain(50..150).collect();
+        assert_eq!(counts(&timsort::Algo, xs), (0, 49 + 98));
+
+        let mut xs = vec![3u8, 1, 2];
+        let counts = Counts::default();
+        keysort::radix_lsd::Algo.sort_by_key_observed(&mut xs, |&x| x, &counts);
+        assert_eq!((xs, counts.swaps(), counts.moves()), (vec![1, 2, 3], 2, 0));
+    }
+
+    #[test]
+    fn test_events_replay() {
+        use rand::Rng;
+
+        // replaying the recording over the input must give the sorted output,
+        // and every comparison must name positions in the slice
+        fn check<F>(xs: &[usize], sort: F)
+        where
+            F: FnOnce(&mut [usize], &Recorder<usize>),
+        {
+            let mut sorted = xs.to_vec();
+            let recorder = Recorder::new();
+            sort(&mut sorted, &recorder);
+            let events = recorder.into_events();
+            let mut replayed = xs.to_vec();
+            replay(&mut replayed, &events);
+            assert_eq!(replayed, sorted);
+            assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+            for e in &events {
+                if let Event::Compare(i, j) | Event::Swap(i, j) = *e {
+                    assert!(i < xs.len() && j < xs.len());
+                }
+            }
+        }
+
+        let mut rand = rand::thread_rng();
+        for n in [0, 1, 2, 10, 100, 500] {
+            let xs: Vec<usize> = (0..n).map(|_| rand.gen_range(0..n.max(1))).collect();
+            check(&xs, |vs, r| bubble::Algo.sort_by_observed(vs, Ord::cmp, r));
+            check(&xs, |vs, r| {
+                insertion::Algo.sort_by_observed(vs, Ord::cmp, r)
+            });
+            check(&xs, |vs, r| {
+                selection::Algo.sort_by_observed(vs, Ord::cmp, r)
+            });
+            check(&xs, |vs, r| {
+                quicksort::Algo.sort_by_observed(vs, Ord::cmp, r)
+            });
+            check(&xs, |vs, r| timsort::Algo.sort_by_observed(vs, Ord::cmp, r));
+            check(&xs, |vs, r| {
+                mergesort::Algo.sort_by_observed(vs, Ord::cmp, r)
+            });
+            check(&xs, |vs, r| {
+                introsort::Algo.sort_by_observed(vs, Ord::cmp, r)
+            });
+            check(&xs, |vs, r| {
+                keysort::radix_msd::Algo.sort_by_key_observed(vs, |&x| x, r)
+            });
+        }
+
+        // comparisons inside a merge point at where the buffered run was
+        let mut xs: Vec<usize> = (0..100).chain(50..150).collect();
+        let recorder = Recorder::new();
+        timsort::Algo.sort_by_observed(&mut xs, Ord::cmp, &recorder);
+        assert!(recorder.into_events().contains(&Event::Compare(100, 51)));
+    }
+}
@@ -0,0 +1,173 @@
+//! Aho–Corasick: a [`Trie`] of patterns with failure links added, which
+//! finds every pattern in a text in one pass. On a mismatch the failure
+//! link moves to the longest proper suffix of the text read so far that is
+//! still a path in the trie, so no atom is read twice.
+
+use std::collections::VecDeque;
+
+use crate::{Key, Trie};
+
+const ROOT: u32 = 0;
+const NONE: u32 = u32::MAX;
+
+#[derive(Debug)]
+struct State<K> {
+    /// the trie's edges out of this state, by atom
+    next: Vec<(K, u32)>,
+    /// the longest proper suffix of this state's path that is a state
+    fail: u32,
+    /// the nearest state down the failure links where a pattern ends
+    dict: u32,
+    /// length of the pattern ending here and its index into `vals`
+    out: Option<(usize, usize)>,
+}
+
+/// The patterns of a [`Trie`], ready to be searched for, from
+/// [`Trie::automaton`].
+#[derive(Debug)]
+pub struct AhoCorasick<K, V> {
+    /// the trie's nodes, in breadth-first order
+    states: Vec<State<K>>,
+    vals: Vec<V>,
+}
+
+/// A pattern found in a text, by atom positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'a, V> {
+    pub start: usize,
+    pub end: usize,
+    pub val: &'a V,
+}
+
+impl<K: Ord + Clone, V: Clone> Trie<K, V> {
+    /// Builds an Aho–Corasick automaton for the keys of the trie, but for
+    /// the empty key, which would match everywhere.
+    pub fn automaton(&self) -> AhoCorasick<K, V> {
+        let mut ac = AhoCorasick {
+            states: Vec::new(),
+            vals: Vec::new(),
+        };
+        let mut q = VecDeque::from([&self.root]);
+        while let Some(n) = q.pop_front() {
+            let id = ac.states.len();
+            let out = n.val.clone().filter(|_| n.count > 0 && id > 0).map(|v| {
+                ac.vals.push(v);
+                (0, ac.vals.len() - 1)
+            });
+            // the children are numbered in the order they are queued
+            let first = id + q.len() + 1;
+            let next = n
+                .children
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    (
+                        c.key.clone().expect("only the root has no key"),
+                        (first + i) as u32,
+                    )
+                })
+                .collect();
+            ac.states.push(State {
+                next,
+                fail: ROOT,
+                dict: NONE,
+                out,
+            });
+            q.extend(&n.children);
+        }
+
+        // breadth first, so the shallower states a failure link can reach
+        // are done first
+        let mut q = VecDeque::from([(ROOT, 0)]);
+        while let Some((s, depth)) = q.pop_front() {
+            if let Some(out) = &mut ac.states[s as usize].out {
+                out.0 = depth;
+            }
+            for i in 0..ac.states[s as usize].next.len() {
+                let (k, child) = ac.states[s as usize].next[i].clone();
+                let fail = match s {
+                    ROOT => ROOT,
+                    _ => ac.step(ac.states[s as usize].fail, &k),
+                };
+                let target = &ac.states[fail as usize];
+                let dict = match target.out {
+                    Some(_) => fail,
+                    None => target.dict,
+                };
+                let c = &mut ac.states[child as usize];
+                c.fail = fail;
+                c.dict = dict;
+                q.push_back((child, depth + 1));
+            }
+        }
+        ac
+    }
+}
+
+impl<K: Ord, V> AhoCorasick<K, V> {
+    /// The state after reading `k` in state `s`.
+    fn step(&self, mut s: u32, k: &K) -> u32 {
+        loop {
+            let state = &self.states[s as usize];
+            if let Ok(i) = state.next.binary_search_by(|(a, _)| a.cmp(k)) {
+                return state.next[i].1;
+            }
+            if s == ROOT {
+                return ROOT;
+            }
+            s = state.fail;
+        }
+    }
+    /// Every occurrence of a pattern in `text`, overlapping ones included,
+    /// by end and then longest first.
+    pub fn find_iter(&self, text: impl Key<K>) -> Matches<'_, K, V, impl Iterator<Item = K>> {
+        self.stream(text.atoms())
+    }
+    /// Like [`find_iter`](Self::find_iter), over atoms read as they come,
+    /// such as the chars of a file being read.
+    pub fn stream<I: IntoIterator<Item = K>>(&self, atoms: I) -> Matches<'_, K, V, I::IntoIter> {
+        Matches {
+            ac: self,
+            atoms: atoms.into_iter(),
+            state: ROOT,
+            at: 0,
+            pending: NONE,
+        }
+    }
+}
+
+/// The matches of an [`AhoCorasick`] in a text, as it is read.
+pub struct Matches<'a, K, V, I> {
+    ac: &'a AhoCorasick<K, V>,
+    atoms: I,
+    state: u32,
+    /// atoms read so far
+    at: usize,
+    /// the next state whose pattern ends at `at`, not yet reported
+    pending: u32,
+}
+
+impl<'a, K: Ord, V, I: Iterator<Item = K>> Iterator for Matches<'a, K, V, I> {
+    type Item = Match<'a, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending == NONE {
+            let k = self.atoms.next()?;
+            self.at += 1;
+            self.state = self.ac.step(self.state, &k);
+            let s = &self.ac.states[self.state as usize];
+            self.pending = match s.out {
+                Some(_) => self.state,
+                None => s.dict,
+            };
+        }
+        let s = &self.ac.states[self.pending as usize];
+        let (len, val) = s.out.expect("pending states end a pattern");
+        self.pending = s.dict;
+        Some(Match {
+            start: self.at - len,
+            end: self.at,
+            val: &self.ac.vals[val],
+        })
+    }
+}
@@ -0,0 +1,161 @@
+//! Memory and speed of the trie layouts on one word list.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use crate::{radix::RadixTrie, Trie};
+
+/// `n` distinct made-up words, strung together from English-looking
+/// syllables, the same for the same `seed`.
+pub fn words(n: usize, seed: u64) -> Vec<String> {
+    const ONSETS: [&str; 20] = [
+        "", "b", "c", "d", "f", "g", "h", "l", "m", "n", "p", "r", "s", "t", "v", "br", "ch", "pr",
+        "st", "tr",
+    ];
+    const VOWELS: [&str; 8] = ["a", "e", "i", "o", "u", "ea", "io", "ou"];
+    const CODAS: [&str; 10] = ["", "", "", "n", "r", "s", "t", "nd", "st", "ng"];
+    // xorshift, so no dependency is needed for a fixed word list
+    let mut state = seed | 1;
+    let mut next = |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % n
+    };
+    let mut seen = HashSet::with_capacity(n);
+    let mut words = Vec::with_capacity(n);
+    while words.len() < n {
+        let syllables = 1 + next(4);
+        let mut word = String::new();
+        for _ in 0..syllables {
+            word.push_str(ONSETS[next(ONSETS.len())]);
+            word.push_str(VOWELS[next(VOWELS.len())]);
+            word.push_str(CODAS[next(CODAS.len())]);
+        }
+        if seen.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// One layout holding a word list.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub name: &'static str,
+    pub nodes: usize,
+    pub bytes: usize,
+    pub build: Duration,
+    /// every word, then every word with a letter added, which misses
+    pub exists: Duration,
+    /// the 3-letter prefix of every hundredth word
+    pub search: Duration,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {:>10} {:>12} {:>10.1?} {:>10.1?} {:>10.1?}",
+            self.name, self.nodes, self.bytes, self.build, self.exists, self.search
+        )
+    }
+}
+
+impl Report {
+    pub const HEADER: &'static str =
+        "layout        nodes        bytes      build     exists     search";
+}
+
+/// Measures each layout on `words`.
+pub fn run(words: &[String]) -> Vec<Report> {
+    vec![
+        measure(
+            "trie",
+            words,
+            |words| {
+                let mut trie: Trie<char, ()> = Trie::new();
+                for w in words {
+                    trie.insert(w.as_str(), ());
+                }
+                trie
+            },
+            |t, w| t.exists(w).is_some(),
+            |t, p| t.search::<String>(p).len(),
+            |t| (t.nodes(), t.memory()),
+        ),
+        measure(
+            "frozen",
+            words,
+            |words| {
+                let mut trie: Trie<char, ()> = Trie::new();
+                for w in words {
+                    trie.insert(w.as_str(), ());
+                }
+                trie.freeze()
+            },
+            |t, w| t.exists(w).is_some(),
+            |t, p| t.search::<String>(p).len(),
+            |t| (t.nodes(), t.memory()),
+        ),
+        measure(
+            "radix",
+            words,
+            |words| {
+                let mut trie: RadixTrie<char, ()> = RadixTrie::new();
+                for w in words {
+                    trie.insert(w.as_str(), ());
+                }
+                trie
+            },
+            |t, w| t.exists(w).is_some(),
+            |t, p| t.search::<String>(p).len(),
+            |t| (t.nodes(), t.memory()),
+        ),
+    ]
+}
+
+fn measure<T>(
+    name: &'static str,
+    words: &[String],
+    build: impl Fn(&[String]) -> T,
+    exists: impl Fn(&T, &str) -> bool,
+    search: impl Fn(&T, &str) -> usize,
+    size: impl Fn(&T) -> (usize, usize),
+) -> Report {
+    let took = Instant::now();
+    let t = build(words);
+    let build = took.elapsed();
+
+    let misses: Vec<String> = words.iter().map(|w| format!("{w}q")).collect();
+    let took = Instant::now();
+    for w in words.iter().chain(&misses) {
+        black_box(exists(&t, w));
+    }
+    let exists = took.elapsed();
+
+    let prefixes: Vec<&str> = words
+        .iter()
+        .step_by(100)
+        .map(|w| &w[..w.len().min(3)])
+        .collect();
+    let took = Instant::now();
+    for p in prefixes {
+        black_box(search(&t, p));
+    }
+    let search = took.elapsed();
+
+    let (nodes, bytes) = size(&t);
+    Report {
+        name,
+        nodes,
+        bytes,
+        build,
+        exists,
+        search,
+    }
+}
@@ -0,0 +1,169 @@
+//! A flat serialized form of a [`Trie`]: the nodes in one array, each
+//! pointing into a table holding every distinct atom once, so a big
+//! dictionary saves and loads without rebuilding it key by key.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{node::Node, Error, Trie};
+
+/// A node in [`Compact::nodes`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Flat {
+    /// index of its atom in [`Compact::labels`], unused at the root
+    pub label: u32,
+    /// how many of the nodes after it are its children; theirs follow them
+    pub children: u32,
+    /// times the key ending here was inserted
+    pub count: usize,
+    /// whether it has a value, which it must exactly when its count is not
+    /// zero
+    pub val: bool,
+}
+
+/// A [`Trie`] flattened in preorder.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Compact<K, V> {
+    /// the distinct atoms, in order
+    pub labels: Vec<K>,
+    /// every node, the root first, each before its children
+    pub nodes: Vec<Flat>,
+    /// the values of the nodes marked as having one, in the order of `nodes`
+    pub vals: Vec<V>,
+}
+
+impl<K: Ord + Clone, V> Trie<K, V> {
+    /// Flattens the trie, borrowing the values.
+    pub fn compact(&self) -> Compact<K, &V> {
+        let mut labels = BTreeMap::new();
+        let mut stack = vec![&self.root];
+        while let Some(n) = stack.pop() {
+            labels.extend(n.key.clone().map(|k| (k, 0)));
+            stack.extend(&n.children);
+        }
+        for (i, id) in labels.values_mut().enumerate() {
+            *id = i as u32;
+        }
+
+        let mut compact = Compact {
+            labels: Vec::with_capacity(labels.len()),
+            nodes: Vec::new(),
+            vals: Vec::new(),
+        };
+        let mut stack = vec![&self.root];
+        while let Some(n) = stack.pop() {
+            compact.nodes.push(Flat {
+                label: n.key.as_ref().map_or(0, |k| labels[k]),
+                children: n.children.len() as u32,
+                count: n.count,
+                val: n.val.is_some(),
+            });
+            compact.vals.extend(n.val.as_ref());
+            // reversed, so the first child is visited next
+            stack.extend(n.children.iter().rev());
+        }
+        compact.labels.extend(labels.into_keys());
+        compact
+    }
+    /// Writes the trie in its [`Compact`] form.
+    pub fn save(&self, w: impl Write) -> Result<(), Error>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        Ok(options().serialize_into(w, &self.compact())?)
+    }
+    /// Reads a trie written by [`Trie::save`].
+    pub fn load(r: impl Read) -> Result<Self, Error>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let compact: Compact<K, V> = options().deserialize_from(r)?;
+        Trie::try_from(compact)
+    }
+}
+
+impl<K: Ord + Clone, V> TryFrom<Compact<K, V>> for Trie<K, V> {
+    type Error = Error;
+
+    /// Rebuilds the trie, recomputing each node's `best`.
+    fn try_from(compact: Compact<K, V>) -> Result<Self, Error> {
+        let Compact {
+            labels,
+            nodes,
+            vals,
+        } = compact;
+        let mut flat = nodes.into_iter();
+        let mut vals = vals.into_iter();
+        // the next node, and how many children it has
+        let mut take = |root: bool| -> Result<(Node<K, V>, u32), Error> {
+            let f = flat.next().ok_or(Error::Corrupt("missing nodes"))?;
+            // every child is a node still to come; checked before reserving
+            // room for them
+            if f.children as usize > flat.len() {
+                return Err(Error::Corrupt("missing nodes"));
+            }
+            let key = match root {
+                true => None,
+                false => Some(
+                    labels
+                        .get(f.label as usize)
+                        .ok_or(Error::Corrupt("label out of range"))?
+                        .clone(),
+                ),
+            };
+            if f.val != (f.count > 0) {
+                return Err(Error::Corrupt("value and count disagree"));
+            }
+            let val = match f.val {
+                false => None,
+                true => Some(vals.next().ok_or(Error::Corrupt("missing values"))?),
+            };
+            let node = Node {
+                children: Vec::with_capacity(f.children as usize),
+                key,
+                val,
+                count: f.count,
+                best: 0,
+            };
+            Ok((node, f.children))
+        };
+
+        // the nodes still taking children, with how many are left
+        let mut stack = vec![take(true)?];
+        let root = loop {
+            let (_, left) = stack.last_mut().expect("the root stays until done");
+            if *left > 0 {
+                *left -= 1;
+                let child = take(false)?;
+                stack.push(child);
+                continue;
+            }
+            let (mut done, _) = stack.pop().expect("just looked");
+            done.update_best();
+            match stack.last_mut() {
+                Some((parent, _)) => {
+                    // lookups binary search the children
+                    if parent.children.last().is_some_and(|c| c.key >= done.key) {
+                        return Err(Error::Corrupt("children out of order"));
+                    }
+                    parent.children.push(done);
+                }
+                None => break done,
+            }
+        };
+        if flat.next().is_some() || vals.next().is_some() {
+            return Err(Error::Corrupt("trailing nodes or values"));
+        }
+        Ok(Trie { root })
+    }
+}
+
+fn options() -> impl Options {
+    // variable-length integers, as most counts and indices are small
+    bincode::DefaultOptions::new()
+}
@@ -0,0 +1,270 @@
+//! An immutable trie in LOUDS form (level-order unary degree sequence):
+//! the shape is a bit vector holding, for each node in breadth-first order,
+//! a one per child and then a zero. A node's children get consecutive ids,
+//! so finding them takes one `select` on the bits and their labels sit side
+//! by side, where the [`Trie`] chases a pointer per node.
+
+use std::collections::VecDeque;
+use std::mem;
+
+use crate::{Key, Trie};
+
+/// Bits with a count of the ones before every block and the position of
+/// every so many zeros, for `rank` and `select` without scanning from the
+/// start.
+#[derive(Debug, Default)]
+struct Bits {
+    words: Vec<u64>,
+    len: usize,
+    /// ones before each block of [`BLOCK`] words
+    ranks: Vec<u32>,
+    /// the position of every [`SAMPLE`]th zero, where `select0` starts
+    samples: Vec<u32>,
+}
+
+const BLOCK: usize = 8;
+const SAMPLE: usize = 64;
+
+impl Bits {
+    fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        if bit {
+            self.words[self.len / 64] |= 1 << (self.len % 64);
+        }
+        self.len += 1;
+    }
+    /// Builds the rank and select directories; call once all bits are
+    /// pushed.
+    fn index(&mut self) {
+        let mut ones = 0;
+        self.ranks = self
+            .words
+            .chunks(BLOCK)
+            .map(|block| {
+                let before = ones;
+                ones += block.iter().map(|w| w.count_ones()).sum::<u32>();
+                before
+            })
+            .collect();
+        self.samples = (0..self.len)
+            .filter(|&i| !self.get(i))
+            .step_by(SAMPLE)
+            .map(|i| i as u32)
+            .collect();
+    }
+    fn get(&self, i: usize) -> bool {
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+    /// Ones before position `i`.
+    fn rank1(&self, i: usize) -> usize {
+        let (word, bit) = (i / 64, i % 64);
+        let block = word / BLOCK;
+        let mut ones = self.ranks[block] as usize;
+        for w in &self.words[block * BLOCK..word] {
+            ones += w.count_ones() as usize;
+        }
+        if bit > 0 {
+            ones += (self.words[word] << (64 - bit)).count_ones() as usize;
+        }
+        ones
+    }
+    /// Position of the zero with `j` zeros before it.
+    fn select0(&self, j: usize) -> usize {
+        let start = self.samples[j / SAMPLE] as usize;
+        let mut left = j % SAMPLE;
+        let mut w = start / 64;
+        let mut zeros = !self.words[w] >> (start % 64) << (start % 64);
+        loop {
+            let n = zeros.count_ones() as usize;
+            if left < n {
+                return w * 64 + select_in(zeros, left);
+            }
+            left -= n;
+            w += 1;
+            zeros = !self.words[w];
+        }
+    }
+    /// Position of the first zero at or after `i`.
+    fn next0(&self, i: usize) -> usize {
+        let mut w = i / 64;
+        let mut zeros = !self.words[w] >> (i % 64) << (i % 64);
+        while zeros == 0 {
+            w += 1;
+            zeros = !self.words[w];
+        }
+        w * 64 + zeros.trailing_zeros() as usize
+    }
+    fn memory(&self) -> usize {
+        self.words.capacity() * mem::size_of::<u64>()
+            + (self.ranks.capacity() + self.samples.capacity()) * mem::size_of::<u32>()
+    }
+}
+
+/// Position of the one in `word` with `n` ones before it.
+fn select_in(word: u64, mut n: usize) -> usize {
+    let mut base = 0;
+    // a byte at a time, then a bit at a time in the byte
+    loop {
+        let ones = (word >> base & 0xff).count_ones() as usize;
+        if n < ones {
+            break;
+        }
+        n -= ones;
+        base += 8;
+    }
+    let mut byte = word >> base & 0xff;
+    for _ in 0..n {
+        byte &= byte - 1;
+    }
+    base + byte.trailing_zeros() as usize
+}
+
+/// A [`Trie`] frozen by [`Trie::freeze`]: the same lookups and prefix
+/// searches, in a fraction of the memory, but no more inserts.
+#[derive(Debug)]
+pub struct FrozenTrie<K, V> {
+    /// the LOUDS bits, one and zero per node: `1^children 0`
+    shape: Bits,
+    /// the atom into each node but the root, by node id minus one
+    labels: Vec<K>,
+    /// whether a key ends at each node, by node id
+    terminal: Bits,
+    /// the count and value of each terminal node, by rank in `terminal`
+    counts: Vec<usize>,
+    vals: Vec<V>,
+}
+
+impl<K: Ord + Clone, V> Trie<K, V> {
+    /// Lays the trie out in LOUDS form, for when it is done changing.
+    pub fn freeze(self) -> FrozenTrie<K, V> {
+        let mut frozen = FrozenTrie {
+            shape: Bits::default(),
+            labels: Vec::new(),
+            terminal: Bits::default(),
+            counts: Vec::new(),
+            vals: Vec::new(),
+        };
+        let mut q = VecDeque::from([self.root]);
+        while let Some(n) = q.pop_front() {
+            for child in &n.children {
+                frozen.shape.push(true);
+                frozen.labels.extend(child.key.clone());
+            }
+            frozen.shape.push(false);
+            frozen.terminal.push(n.count > 0);
+            if n.count > 0 {
+                frozen.counts.push(n.count);
+                frozen.vals.extend(n.val);
+            }
+            q.extend(n.children);
+        }
+        frozen.shape.index();
+        frozen.terminal.index();
+        frozen
+    }
+}
+
+impl<K: Ord + Clone, V> FrozenTrie<K, V> {
+    /// The ids of node `x`'s children.
+    fn children(&self, x: usize) -> std::ops::Range<usize> {
+        // node x's ones start after the zero ending node x - 1; the ones
+        // before them, plus the root, number the first child
+        let start = if x == 0 {
+            0
+        } else {
+            self.shape.select0(x - 1) + 1
+        };
+        let end = self.shape.next0(start);
+        let first = start - x + 1;
+        first..first + (end - start)
+    }
+    fn node(&self, key: impl Key<K>) -> Option<usize> {
+        let mut x = 0;
+        for k in key.atoms() {
+            let ids = self.children(x);
+            let i = self.labels[ids.start - 1..ids.end - 1]
+                .binary_search(&k)
+                .ok()?;
+            x = ids.start + i;
+        }
+        Some(x)
+    }
+    /// Index of node `x` into `counts` and `vals`, if a key ends there.
+    fn terminal(&self, x: usize) -> Option<usize> {
+        self.terminal.get(x).then(|| self.terminal.rank1(x))
+    }
+    /// How many times `key` was inserted, if it was.
+    pub fn exists(&self, key: impl Key<K>) -> Option<usize> {
+        self.terminal(self.node(key)?).map(|t| self.counts[t])
+    }
+    pub fn get(&self, key: impl Key<K>) -> Option<&V> {
+        self.vals.get(self.terminal(self.node(key)?)?)
+    }
+    /// The keys starting with `prefix` with their counts, in key order.
+    fn collect(&self, prefix: impl Key<K>) -> Vec<(usize, Vec<K>)> {
+        fn walk<K: Ord + Clone, V>(
+            t: &FrozenTrie<K, V>,
+            x: usize,
+            path: &mut Vec<K>,
+            out: &mut Vec<(usize, Vec<K>)>,
+        ) {
+            if let Some(i) = t.terminal(x) {
+                out.push((t.counts[i], path.clone()));
+            }
+            for child in t.children(x) {
+                path.push(t.labels[child - 1].clone());
+                walk(t, child, path, out);
+                path.pop();
+            }
+        }
+        let mut path: Vec<K> = prefix.atoms().collect();
+        let mut out = Vec::new();
+        if let Some(x) = self.node(&path[..]) {
+            walk(self, x, &mut path, &mut out);
+        }
+        out
+    }
+    /// The keys starting with `prefix`, least inserted first, like
+    /// [`Trie::search`].
+    pub fn search<Q>(&self, prefix: impl Key<K>) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        let mut found = self.collect(prefix);
+        found.sort();
+        found
+            .into_iter()
+            .map(|(_, key)| key.into_iter().collect())
+            .collect()
+    }
+    /// The `k` most inserted keys starting with `prefix`, like
+    /// [`Trie::complete`], though without cached counts to prune by.
+    pub fn complete<Q>(&self, prefix: impl Key<K>, k: usize) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        let mut found = self.collect(prefix);
+        found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        found
+            .into_iter()
+            .take(k)
+            .map(|(_, key)| key.into_iter().collect())
+            .collect()
+    }
+    /// Nodes, the root included.
+    pub fn nodes(&self) -> usize {
+        self.labels.len() + 1
+    }
+    /// Bytes taken by the bits, labels and counts, not counting what the
+    /// values own.
+    pub fn memory(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.shape.memory()
+            + self.terminal.memory()
+            + self.labels.capacity() * mem::size_of::<K>()
+            + self.counts.capacity() * mem::size_of::<usize>()
+            + self.vals.capacity() * mem::size_of::<V>()
+    }
+}
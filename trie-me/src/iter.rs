@@ -0,0 +1,254 @@
+//! Walking a [`Trie`] in key order, and building one from keys.
+
+use std::ops::{Bound, RangeBounds};
+use std::{slice, vec};
+
+use crate::{node::Node, Key, Trie};
+
+/// A node being walked: its value if not yet yielded, and the children
+/// left to visit.
+struct Frame<'a, K, V> {
+    val: Option<&'a V>,
+    children: slice::Iter<'a, Node<K, V>>,
+}
+
+impl<'a, K, V> Frame<'a, K, V> {
+    fn new(n: &'a Node<K, V>) -> Self {
+        Frame {
+            val: n.val.as_ref().filter(|_| n.count > 0),
+            children: n.children.iter(),
+        }
+    }
+}
+
+/// Keys and values in key order, from [`Trie::iter`] and
+/// [`Trie::iter_prefix`].
+pub struct Iter<'a, K, V> {
+    stack: Vec<Frame<'a, K, V>>,
+    path: Vec<K>,
+}
+
+impl<'a, K: Clone, V> Iterator for Iter<'a, K, V> {
+    type Item = (Vec<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            if let Some(val) = frame.val.take() {
+                return Some((self.path.clone(), val));
+            }
+            match frame.children.next() {
+                Some(child) => {
+                    self.path.extend(child.key.clone());
+                    self.stack.push(Frame::new(child));
+                }
+                None => {
+                    self.stack.pop();
+                    // the bottom frame's path is the prefix, which stays
+                    if !self.stack.is_empty() {
+                        self.path.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Keys and values between two bounds, in key order, from [`Trie::range`].
+pub struct Range<'a, K, V> {
+    iter: Iter<'a, K, V>,
+    end: Bound<Vec<K>>,
+}
+
+impl<'a, K: Ord + Clone, V> Iterator for Range<'a, K, V> {
+    type Item = (Vec<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, val) = self.iter.next()?;
+        let within = match &self.end {
+            Bound::Included(end) => key <= *end,
+            Bound::Excluded(end) => key < *end,
+            Bound::Unbounded => true,
+        };
+        if within {
+            Some((key, val))
+        } else {
+            // everything after is past the end too
+            self.iter.stack.clear();
+            None
+        }
+    }
+}
+
+/// A node being taken apart, like [`Frame`] but owning.
+type OwnedFrame<K, V> = (Option<V>, vec::IntoIter<Node<K, V>>);
+
+/// Owned keys and values in key order, from [`Trie::into_iter`].
+pub struct IntoIter<K, V> {
+    stack: Vec<OwnedFrame<K, V>>,
+    path: Vec<K>,
+}
+
+impl<K: Clone, V> Iterator for IntoIter<K, V> {
+    type Item = (Vec<K>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (val, children) = self.stack.last_mut()?;
+            if let Some(val) = val.take() {
+                return Some((self.path.clone(), val));
+            }
+            match children.next() {
+                Some(child) => {
+                    self.path.extend(child.key);
+                    let val = child.val.filter(|_| child.count > 0);
+                    self.stack.push((val, child.children.into_iter()));
+                }
+                None => {
+                    self.stack.pop();
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> Trie<K, V> {
+    /// Every key and its value, in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            stack: vec![Frame::new(&self.root)],
+            path: Vec::new(),
+        }
+    }
+    pub fn keys(&self) -> impl Iterator<Item = Vec<K>> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+    /// The keys starting with `prefix` and their values, in key order.
+    pub fn iter_prefix(&self, prefix: impl Key<K>) -> Iter<'_, K, V> {
+        let mut path = Vec::new();
+        let mut cur = &self.root;
+        for k in prefix.atoms() {
+            match cur.find(&k) {
+                Ok(i) => cur = &cur.children[i],
+                Err(_) => {
+                    return Iter {
+                        stack: Vec::new(),
+                        path,
+                    }
+                }
+            }
+            path.push(k);
+        }
+        Iter {
+            stack: vec![Frame::new(cur)],
+            path,
+        }
+    }
+    /// The keys within `range` and their values, in key order. Keys
+    /// compare atom by atom, a prefix before what it starts.
+    pub fn range<T>(&self, range: impl RangeBounds<T>) -> Range<'_, K, V>
+    where
+        T: Key<K> + Clone,
+    {
+        let bound = |b: Bound<&T>| match b {
+            Bound::Included(t) => Bound::Included(t.clone().atoms().collect::<Vec<K>>()),
+            Bound::Excluded(t) => Bound::Excluded(t.clone().atoms().collect()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let (start, end) = (bound(range.start_bound()), bound(range.end_bound()));
+        let (start, inclusive) = match start {
+            Bound::Included(s) => (s, true),
+            Bound::Excluded(s) => (s, false),
+            Bound::Unbounded => {
+                return Range {
+                    iter: self.iter(),
+                    end,
+                }
+            }
+        };
+
+        // seek: follow `start` down, leaving each frame past what sorts
+        // before it
+        let mut iter = Iter {
+            stack: vec![Frame::new(&self.root)],
+            path: Vec::new(),
+        };
+        let mut cur = &self.root;
+        for k in start.iter() {
+            let frame = iter.stack.last_mut().expect("seeking");
+            // the path so far is a proper prefix of `start`, so less
+            frame.val = None;
+            match cur.find(k) {
+                Ok(i) => {
+                    frame.children = cur.children[i + 1..].iter();
+                    cur = &cur.children[i];
+                    iter.path.push(k.clone());
+                    iter.stack.push(Frame::new(cur));
+                }
+                Err(i) => {
+                    frame.children = cur.children[i..].iter();
+                    return Range { iter, end };
+                }
+            }
+        }
+        if !inclusive {
+            iter.stack.last_mut().expect("seeking").val = None;
+        }
+        Range { iter, end }
+    }
+}
+
+impl<'a, K: Ord + Clone, V> IntoIterator for &'a Trie<K, V> {
+    type Item = (Vec<K>, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K: Clone, V> IntoIterator for Trie<K, V> {
+    type Item = (Vec<K>, V);
+    type IntoIter = IntoIter<K, V>;
+
+    /// Every key and its value, in key order.
+    fn into_iter(self) -> IntoIter<K, V> {
+        let root = self.root;
+        let val = root.val.filter(|_| root.count > 0);
+        IntoIter {
+            stack: vec![(val, root.children.into_iter())],
+            path: Vec::new(),
+        }
+    }
+}
+
+/// Inserts each key with the default value, counting repeats.
+impl<K, V, Q> FromIterator<Q> for Trie<K, V>
+where
+    K: Ord + Clone,
+    V: Default,
+    Q: Key<K>,
+{
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        let mut trie = Trie::new();
+        trie.extend(iter);
+        trie
+    }
+}
+
+impl<K, V, Q> Extend<Q> for Trie<K, V>
+where
+    K: Ord + Clone,
+    V: Default,
+    Q: Key<K>,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key, V::default());
+        }
+    }
+}
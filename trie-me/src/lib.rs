@@ -0,0 +1,1463 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt::Display;
+
+use node::Node;
+use serde::{Deserialize, Serialize};
+
+pub mod aho;
+pub mod bench;
+pub mod compact;
+pub mod frozen;
+pub mod iter;
+pub mod normalize;
+pub mod radix;
+pub mod shared;
+
+#[derive(Debug)]
+pub enum Error {
+    /// reading or writing a saved trie failed
+    Encoding(bincode::Error),
+    /// a saved trie decoded, but does not describe a trie
+    Corrupt(&'static str),
+}
+
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::Encoding(e)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Encoding(e) => write!(f, "{e}"),
+            Error::Corrupt(why) => write!(f, "corrupt trie: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+mod node {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Node<K, V> {
+        pub children: Vec<Node<K, V>>,
+        pub key: Option<K>,
+        pub val: Option<V>,
+        /// times the key ending here was inserted
+        pub count: usize,
+        /// the highest `count` at or below this node
+        pub best: usize,
+    }
+
+    impl<K, V> Default for Node<K, V> {
+        fn default() -> Self {
+            Node {
+                children: Vec::new(),
+                key: None,
+                val: None,
+                count: 0,
+                best: 0,
+            }
+        }
+    }
+
+    impl<K: Ord, V> Node<K, V> {
+        pub fn new() -> Self {
+            Node {
+                ..Default::default()
+            }
+        }
+        pub fn with_key(k: K) -> Self {
+            Node {
+                key: Some(k),
+                ..Default::default()
+            }
+        }
+        /// No key ends here and nothing hangs below, so the node can go.
+        pub fn is_empty(&self) -> bool {
+            self.count == 0 && self.children.is_empty()
+        }
+        /// Recomputes `best` from this node and its children.
+        pub fn update_best(&mut self) {
+            self.best = self
+                .children
+                .iter()
+                .map(|n| n.best)
+                .fold(self.count, usize::max);
+        }
+        /// Where the child keyed `k` is, or would go.
+        pub fn find(&self, k: &K) -> Result<usize, usize> {
+            self.children
+                .binary_search_by(|n| n.key.as_ref().cmp(&Some(k)))
+        }
+    }
+}
+
+/// Something that splits into the atoms a [`Trie`] is keyed by: a `&str`
+/// into chars or bytes, a slice, array or `Vec` into its elements.
+pub trait Key<K> {
+    fn atoms(self) -> impl Iterator<Item = K>;
+}
+
+impl Key<char> for &str {
+    fn atoms(self) -> impl Iterator<Item = char> {
+        self.chars()
+    }
+}
+
+impl Key<u8> for &str {
+    fn atoms(self) -> impl Iterator<Item = u8> {
+        self.bytes()
+    }
+}
+
+impl<K: Clone> Key<K> for &[K] {
+    fn atoms(self) -> impl Iterator<Item = K> {
+        self.iter().cloned()
+    }
+}
+
+impl<K: Clone, const N: usize> Key<K> for &[K; N] {
+    fn atoms(self) -> impl Iterator<Item = K> {
+        self.iter().cloned()
+    }
+}
+
+impl<K, const N: usize> Key<K> for [K; N] {
+    fn atoms(self) -> impl Iterator<Item = K> {
+        self.into_iter()
+    }
+}
+
+impl<K> Key<K> for Vec<K> {
+    fn atoms(self) -> impl Iterator<Item = K> {
+        self.into_iter()
+    }
+}
+
+/// A map from sequences of `K` to `V`, which also counts how often each key
+/// was inserted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trie<K, V> {
+    pub root: Node<K, V>,
+}
+
+impl<K, V> Default for Trie<K, V> {
+    fn default() -> Self {
+        Trie {
+            root: Node::default(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> Trie<K, V> {
+    pub fn new() -> Self {
+        Trie {
+            ..Default::default()
+        }
+    }
+    /// Stores `val` under `key`, returning the value it replaces.
+    pub fn insert(&mut self, key: impl Key<K>, val: V) -> Option<V> {
+        // returns the new count, to raise `best` on the way back up
+        fn insert<K: Ord, V>(
+            cur: &mut Node<K, V>,
+            mut atoms: impl Iterator<Item = K>,
+            val: V,
+        ) -> (usize, Option<V>) {
+            let (count, old) = match atoms.next() {
+                None => {
+                    cur.count += 1;
+                    (cur.count, cur.val.replace(val))
+                }
+                Some(k) => {
+                    let i = cur.find(&k).unwrap_or_else(|i| {
+                        cur.children.insert(i, Node::with_key(k));
+                        i
+                    });
+                    insert(&mut cur.children[i], atoms, val)
+                }
+            };
+            cur.best = cur.best.max(count);
+            (count, old)
+        }
+        insert(&mut self.root, key.atoms(), val).1
+    }
+    fn node(&self, key: impl Key<K>) -> Option<&Node<K, V>> {
+        let mut cur = &self.root;
+        for k in key.atoms() {
+            match cur.find(&k) {
+                Ok(i) => {
+                    cur = &cur.children[i];
+                }
+                Err(_) => {
+                    return None;
+                }
+            }
+        }
+        Some(cur)
+    }
+    pub fn exists(&self, key: impl Key<K>) -> Option<&Node<K, V>> {
+        self.node(key).filter(|n| n.count > 0)
+    }
+    pub fn get(&self, key: impl Key<K>) -> Option<&V> {
+        self.node(key)?.val.as_ref()
+    }
+    /// The longest inserted key that `text` starts with, as the number of
+    /// atoms it takes, and its value: the route a path falls under, or the
+    /// next token of a greedy tokenizer.
+    pub fn longest_prefix_of(&self, text: impl Key<K>) -> Option<(usize, &V)> {
+        let mut cur = &self.root;
+        let mut longest = cur.val.as_ref().map(|v| (0, v));
+        for (i, k) in text.atoms().enumerate() {
+            match cur.find(&k) {
+                Ok(c) => cur = &cur.children[c],
+                Err(_) => break,
+            }
+            if let Some(v) = &cur.val {
+                longest = Some((i + 1, v));
+            }
+        }
+        longest
+    }
+    pub fn get_mut(&mut self, key: impl Key<K>) -> Option<&mut V> {
+        let mut cur = &mut self.root;
+        for k in key.atoms() {
+            let i = cur.find(&k).ok()?;
+            cur = &mut cur.children[i];
+        }
+        cur.val.as_mut()
+    }
+    /// The slot for `key`, to read or fill in place. Nothing is added to
+    /// the trie until a vacant entry is inserted into.
+    pub fn entry(&mut self, key: impl Key<K>) -> Entry<'_, K, V> {
+        let mut cur = &mut self.root;
+        let mut atoms = key.atoms();
+        while let Some(k) = atoms.next() {
+            match cur.find(&k) {
+                Ok(i) => cur = &mut cur.children[i],
+                Err(_) => {
+                    let rest = std::iter::once(k).chain(atoms).collect();
+                    return Entry::Vacant(VacantEntry { node: cur, rest });
+                }
+            }
+        }
+        if cur.count > 0 {
+            Entry::Occupied(OccupiedEntry { node: cur })
+        } else {
+            Entry::Vacant(VacantEntry {
+                node: cur,
+                rest: Vec::new(),
+            })
+        }
+    }
+    /// Removes one insertion of `key`, forgetting it and its value once
+    /// none are left and pruning the branches that held only it. Returns
+    /// whether `key` was there.
+    pub fn remove(&mut self, key: impl Key<K>) -> bool {
+        fn remove<K: Ord, V>(cur: &mut Node<K, V>, mut atoms: impl Iterator<Item = K>) -> bool {
+            let Some(k) = atoms.next() else {
+                if cur.count == 0 {
+                    return false;
+                }
+                cur.count -= 1;
+                if cur.count == 0 {
+                    cur.val = None;
+                }
+                cur.update_best();
+                return true;
+            };
+            let Ok(i) = cur.find(&k) else {
+                return false;
+            };
+            let found = remove(&mut cur.children[i], atoms);
+            if cur.children[i].is_empty() {
+                cur.children.remove(i);
+            }
+            cur.update_best();
+            found
+        }
+        remove(&mut self.root, key.atoms())
+    }
+    pub fn clear(&mut self) {
+        self.root = Node::new();
+    }
+    /// Keeps only the keys for which `f(key, value, count)` holds, pruning
+    /// what the others leave empty.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[K], &mut V, usize) -> bool,
+    {
+        fn retain<K, V, F>(cur: &mut Node<K, V>, path: &mut Vec<K>, f: &mut F)
+        where
+            K: Ord + Clone,
+            F: FnMut(&[K], &mut V, usize) -> bool,
+        {
+            for child in &mut cur.children {
+                path.extend(child.key.clone());
+                retain(child, path, f);
+                path.pop();
+            }
+            cur.children.retain(|n| !n.is_empty());
+            if let Some(val) = &mut cur.val {
+                if !f(path, val, cur.count) {
+                    cur.count = 0;
+                    cur.val = None;
+                }
+            }
+            cur.update_best();
+        }
+        retain(&mut self.root, &mut Vec::new(), &mut f);
+    }
+    /// Nodes, the root included.
+    pub fn nodes(&self) -> usize {
+        fn nodes<K, V>(n: &Node<K, V>) -> usize {
+            1 + n.children.iter().map(nodes).sum::<usize>()
+        }
+        nodes(&self.root)
+    }
+    /// Bytes taken by the nodes, not counting what the values own.
+    pub fn memory(&self) -> usize {
+        fn memory<K, V>(n: &Node<K, V>) -> usize {
+            n.children.capacity() * std::mem::size_of::<Node<K, V>>()
+                + n.children.iter().map(memory).sum::<usize>()
+        }
+        std::mem::size_of::<Self>() + memory(&self.root)
+    }
+    /// The keys starting with `prefix`, least inserted first, collected
+    /// into `Q`: `String` for a char trie, `Vec<K>` for any.
+    pub fn search<Q>(&self, prefix: impl Key<K>) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        let mut path: Vec<K> = Vec::new();
+        let mut cur = &self.root;
+        for k in prefix.atoms() {
+            match cur.find(&k) {
+                Ok(i) => {
+                    cur = &cur.children[i];
+                }
+                Err(_) => return Default::default(),
+            }
+            path.push(k);
+        }
+        fn collect<K: Clone, V>(n: &Node<K, V>, path: &mut Vec<K>, out: &mut Vec<(usize, Vec<K>)>) {
+            if n.count > 0 {
+                out.push((n.count, path.clone()));
+            }
+            for child in &n.children {
+                path.extend(child.key.clone());
+                collect(child, path, out);
+                path.pop();
+            }
+        }
+        let mut results = Vec::new();
+        collect(cur, &mut path, &mut results);
+        results.sort();
+        results
+            .into_iter()
+            .map(|(_, key)| key.into_iter().collect())
+            .collect()
+    }
+    /// The `k` most inserted keys starting with `prefix`, most first, ties
+    /// in key order. Subtrees are opened best first by their cached `best`
+    /// count, so only the branches that can place are visited.
+    pub fn complete<Q>(&self, prefix: impl Key<K>, k: usize) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        let path: Vec<K> = prefix.atoms().collect();
+        let Some(start) = self.node(&path[..]) else {
+            return Vec::new();
+        };
+        let mut results = Vec::new();
+        let mut q = BinaryHeap::new();
+        q.push(Candidate {
+            count: start.best,
+            path,
+            node: start,
+            key_only: false,
+        });
+        while results.len() < k {
+            let Some(c) = q.pop() else { break };
+            if c.count == 0 {
+                break;
+            }
+            if c.key_only {
+                results.push(c.path.into_iter().collect());
+                continue;
+            }
+            for child in &c.node.children {
+                let mut path = c.path.clone();
+                path.extend(child.key.clone());
+                q.push(Candidate {
+                    count: child.best,
+                    path,
+                    node: child,
+                    key_only: false,
+                });
+            }
+            if c.node.count > 0 {
+                q.push(Candidate {
+                    count: c.node.count,
+                    key_only: true,
+                    ..c
+                });
+            }
+        }
+        results
+    }
+    /// The keys within `max_edits` insertions, deletions or substitutions
+    /// of `query`, with their distance, closest first, then most inserted,
+    /// then in key order.
+    pub fn search_fuzzy<Q>(&self, query: impl Key<K>, max_edits: usize) -> Vec<(Q, usize)>
+    where
+        Q: FromIterator<K>,
+    {
+        let query: Vec<K> = query.atoms().collect();
+        let mut found = self.fuzzy(&query, max_edits, false);
+        found.sort();
+        found
+            .into_iter()
+            .map(|(dist, _, key)| (key.into_iter().collect(), dist))
+            .collect()
+    }
+    /// Autocomplete for a mistyped prefix: the `k` best keys that start
+    /// with something within `max_edits` of `prefix`, ranked like
+    /// [`search_fuzzy`](Self::search_fuzzy).
+    pub fn complete_fuzzy<Q>(
+        &self,
+        prefix: impl Key<K>,
+        max_edits: usize,
+        k: usize,
+    ) -> Vec<(Q, usize)>
+    where
+        Q: FromIterator<K>,
+    {
+        let prefix: Vec<K> = prefix.atoms().collect();
+        let mut found = self.fuzzy(&prefix, max_edits, true);
+        found.sort();
+        found
+            .into_iter()
+            .take(k)
+            .map(|(dist, _, key)| (key.into_iter().collect(), dist))
+            .collect()
+    }
+    /// Walks the trie with a row of the Levenshtein table per node: entry
+    /// `j` of a node's row is the distance from its path to `query[..j]`.
+    /// Returns `(distance, Reverse(count), key)` for every match; with
+    /// `prefix`, a key matches through its closest prefix.
+    fn fuzzy(
+        &self,
+        query: &[K],
+        max_edits: usize,
+        prefix: bool,
+    ) -> Vec<(usize, Reverse<usize>, Vec<K>)> {
+        struct Walk<'q, K> {
+            query: &'q [K],
+            max_edits: usize,
+            prefix: bool,
+            path: Vec<K>,
+            found: Vec<(usize, Reverse<usize>, Vec<K>)>,
+        }
+
+        impl<K: Ord + Clone> Walk<'_, K> {
+            // `best` is the distance of the closest prefix so far, in prefix mode
+            fn visit<V>(&mut self, n: &Node<K, V>, row: &[usize], best: usize) {
+                let m = self.query.len();
+                let best = if self.prefix {
+                    best.min(row[m])
+                } else {
+                    row[m]
+                };
+                if n.count > 0 && best <= self.max_edits {
+                    self.found.push((best, Reverse(n.count), self.path.clone()));
+                }
+                // nothing below can get closer than the row's minimum
+                let reachable = row.iter().min().copied().unwrap_or(0).min(best);
+                if reachable > self.max_edits {
+                    return;
+                }
+                let mut next = vec![0; m + 1];
+                for child in &n.children {
+                    let Some(k) = &child.key else { continue };
+                    next[0] = row[0] + 1;
+                    for j in 1..=m {
+                        let substitute = row[j - 1] + usize::from(self.query[j - 1] != *k);
+                        next[j] = substitute.min(row[j] + 1).min(next[j - 1] + 1);
+                    }
+                    self.path.push(k.clone());
+                    self.visit(child, &next, best);
+                    self.path.pop();
+                }
+            }
+        }
+
+        let mut walk = Walk {
+            query,
+            max_edits,
+            prefix,
+            path: Vec::new(),
+            found: Vec::new(),
+        };
+        let row: Vec<usize> = (0..=query.len()).collect();
+        walk.visit(&self.root, &row, usize::MAX);
+        walk.found
+    }
+}
+
+/// A subtree or a single key waiting in [`Trie::complete`]'s queue.
+struct Candidate<'a, K, V> {
+    /// the best count in the subtree, or the key's own count
+    count: usize,
+    path: Vec<K>,
+    node: &'a Node<K, V>,
+    /// only the key at `node` itself, not its subtree
+    key_only: bool,
+}
+
+impl<K: Ord, V> Candidate<'_, K, V> {
+    // most inserted first, then in key order; a subtree's path is a prefix
+    // of its keys, so it opens before any key it could beat
+    fn rank(&self) -> (usize, Reverse<&[K]>, bool) {
+        (self.count, Reverse(&self.path), !self.key_only)
+    }
+}
+
+impl<K: Ord, V> PartialEq for Candidate<'_, K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+
+impl<K: Ord, V> Eq for Candidate<'_, K, V> {}
+
+impl<K: Ord, V> PartialOrd for Candidate<'_, K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> Ord for Candidate<'_, K, V> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+/// A slot in a [`Trie`], from [`Trie::entry`].
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    node: &'a mut Node<K, V>,
+}
+
+/// The deepest node on the way to the key, and the atoms still missing.
+pub struct VacantEntry<'a, K, V> {
+    node: &'a mut Node<K, V>,
+    rest: Vec<K>,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(e) = &mut self {
+            f(e.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn get(&self) -> &V {
+        self.node.val.as_ref().expect("occupied")
+    }
+    pub fn get_mut(&mut self) -> &mut V {
+        self.node.val.as_mut().expect("occupied")
+    }
+    pub fn into_mut(self) -> &'a mut V {
+        self.node.val.as_mut().expect("occupied")
+    }
+    /// Times the key was inserted.
+    pub fn count(&self) -> usize {
+        self.node.count
+    }
+    /// Replaces the value, leaving the count alone.
+    pub fn insert(&mut self, val: V) -> V {
+        self.node.val.replace(val).expect("occupied")
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn insert(self, val: V) -> &'a mut V {
+        // the nodes above `self.node` already lead to a key, so their
+        // `best` is at least the 1 this one adds
+        let mut cur = self.node;
+        cur.best = cur.best.max(1);
+        for k in self.rest {
+            // the first atom is missing, so are all after it
+            let i = cur.find(&k).unwrap_err();
+            cur.children.insert(i, Node::with_key(k));
+            cur = &mut cur.children[i];
+            cur.best = 1;
+        }
+        cur.count = 1;
+        cur.val.insert(val)
+    }
+}
+
+impl<K: Display, V> Display for Trie<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut q = VecDeque::new();
+        q.push_back(&self.root);
+
+        while !q.is_empty() {
+            for _ in 0..q.len() {
+                if let Some(node) = q.pop_front() {
+                    for child in &node.children {
+                        write!(f, "{} ", child.key.as_ref().unwrap())?;
+                        if !child.children.is_empty() {
+                            q.push_back(child);
+                        }
+                    }
+                }
+            }
+
+            if !q.is_empty() {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::Options;
+    use compact::Compact;
+
+    #[test]
+    fn it_works() -> Result<(), Error> {
+        let mut trie: Trie<char, ()> = Trie::new();
+        trie.insert("potatoe", ());
+        trie.insert("province", ());
+        trie.insert("profile", ());
+        trie.insert("profileur", ());
+        trie.insert("profiteur", ());
+        trie.insert("prof", ());
+        trie.insert("professor", ());
+        trie.insert("provinces", ());
+        trie.insert("providence", ());
+        trie.insert("providences", ());
+
+        dbg!(trie.search::<String>("prof"));
+        println!("{trie}");
+        Ok(())
+    }
+
+    #[test]
+    fn remove_prunes() {
+        let mut trie: Trie<char, ()> = Trie::new();
+        for s in ["ab", "ac", "abc", "b", "ab"] {
+            trie.insert(s, ());
+        }
+        assert_eq!(trie.to_string(), "a b \nb c \nc ");
+
+        // "ab" went in twice
+        assert!(trie.remove("ab"));
+        assert!(trie.exists("ab").is_some());
+        assert!(trie.remove("ab"));
+        assert!(trie.exists("ab").is_none());
+        assert!(!trie.remove("ab"));
+        // still on the way to "abc"
+        assert_eq!(trie.to_string(), "a b \nb c \nc ");
+
+        assert!(trie.remove("abc"));
+        assert_eq!(trie.to_string(), "a b \nc ");
+        assert!(!trie.remove("a"));
+        assert!(!trie.remove("abd"));
+        assert!(trie.remove("ac"));
+        assert_eq!(trie.to_string(), "b ");
+        assert_eq!(trie.search::<String>(""), ["b"]);
+
+        trie.clear();
+        assert_eq!(trie.to_string(), "");
+        assert!(trie.search::<String>("").is_empty());
+    }
+
+    #[test]
+    fn retain_prunes() {
+        let mut trie: Trie<char, ()> = Trie::new();
+        for s in [
+            "prof",
+            "profile",
+            "profileur",
+            "province",
+            "potatoe",
+            "prof",
+        ] {
+            trie.insert(s, ());
+        }
+        trie.retain(|key, _, count| count > 1 || key.ends_with(&['e', 'u', 'r']));
+        assert_eq!(trie.search::<String>(""), ["profileur", "prof"]);
+        assert_eq!(trie.to_string(), "p \nr \no \nf \ni \nl \ne \nu \nr ");
+
+        trie.retain(|_, _, _| false);
+        assert_eq!(trie.to_string(), "");
+    }
+
+    #[test]
+    fn generic_keys() {
+        // bytes, with a payload
+        let mut trie: Trie<u8, usize> = Trie::new();
+        assert_eq!(trie.insert("prof", 1), None);
+        assert_eq!(trie.insert("prof", 2), Some(1));
+        assert_eq!(trie.get("prof"), Some(&2));
+        assert_eq!(trie.get("pro"), None);
+        *trie.get_mut("prof").unwrap() += 10;
+        assert_eq!(trie.get("prof"), Some(&12));
+        assert_eq!(trie.exists("prof").map(|n| n.count), Some(2));
+        assert_eq!(trie.search::<Vec<u8>>("pr"), [b"prof"]);
+
+        // path segments
+        let mut routes: Trie<&str, &str> = Trie::new();
+        routes.insert(["api", "users"], "list users");
+        routes.insert(vec!["api", "users", "new"], "add a user");
+        assert_eq!(routes.get(["api", "users"]), Some(&"list users"));
+        assert_eq!(routes.get(&["api"][..]), None);
+        assert_eq!(
+            routes.search::<Vec<_>>(["api"]),
+            [vec!["api", "users"], vec!["api", "users", "new"]]
+        );
+
+        // IP octets, longest match first by hand
+        let mut nets: Trie<u8, &str> = Trie::new();
+        nets.insert([10], "private");
+        nets.insert([10, 1, 2], "lab");
+        assert_eq!(nets.get([10, 1, 2]), Some(&"lab"));
+        assert_eq!(nets.get(&[10u8][..]), Some(&"private"));
+    }
+
+    #[test]
+    fn entries() {
+        let mut trie: Trie<char, usize> = Trie::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            *trie.entry(word).or_default() += 1;
+        }
+        assert_eq!(trie.get("the"), Some(&3));
+        assert_eq!(trie.get("and"), Some(&2));
+        assert_eq!(trie.get("cat"), Some(&1));
+        // entries fill in place rather than counting insertions
+        assert_eq!(trie.exists("the").map(|n| n.count), Some(1));
+
+        trie.entry("cat").and_modify(|n| *n += 10).or_insert(0);
+        trie.entry("cow").and_modify(|n| *n += 10).or_insert(0);
+        assert_eq!(trie.get("cat"), Some(&11));
+        assert_eq!(trie.get("cow"), Some(&0));
+
+        // a vacant entry left alone adds nothing, even inside a key
+        let before = trie.to_string();
+        assert!(matches!(trie.entry("th"), Entry::Vacant(_)));
+        assert!(matches!(trie.entry("zebra"), Entry::Vacant(_)));
+        assert_eq!(trie.to_string(), before);
+
+        if let Entry::Occupied(mut e) = trie.entry("hat") {
+            assert_eq!(e.insert(5), 1);
+            assert_eq!(e.count(), 1);
+        }
+        assert_eq!(trie.get("hat"), Some(&5));
+    }
+
+    /// The first `k` of every key under `prefix`, most inserted first and
+    /// then in key order, found the slow way.
+    fn complete_by_scan(trie: &Trie<char, ()>, prefix: &str, k: usize) -> Vec<String> {
+        let mut all: Vec<(usize, String)> = trie
+            .search::<String>(prefix)
+            .into_iter()
+            .map(|s| (trie.exists(s.as_str()).unwrap().count, s))
+            .collect();
+        all.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        all.into_iter().take(k).map(|(_, s)| s).collect()
+    }
+
+    #[test]
+    fn complete_ranks() {
+        let mut trie: Trie<char, ()> = Trie::new();
+        for (word, times) in [
+            ("potatoe", 1),
+            ("province", 4),
+            ("profile", 2),
+            ("profileur", 1),
+            ("profiteur", 1),
+            ("prof", 6),
+            ("professor", 3),
+            ("provinces", 2),
+            ("providence", 2),
+            ("providences", 5),
+        ] {
+            for _ in 0..times {
+                trie.insert(word, ());
+            }
+        }
+        assert_eq!(
+            trie.complete::<String>("pro", 4),
+            ["prof", "providences", "province", "professor"]
+        );
+        // ties in key order
+        assert_eq!(
+            trie.complete::<String>("pro", 7)[4..],
+            ["profile", "providence", "provinces"]
+        );
+        assert_eq!(
+            trie.complete::<String>("profi", 10),
+            ["profile", "profileur", "profiteur"]
+        );
+        assert_eq!(trie.complete::<String>("", 1), ["prof"]);
+        assert!(trie.complete::<String>("pro", 0).is_empty());
+        assert!(trie.complete::<String>("x", 3).is_empty());
+
+        // the cached counts follow removals
+        for _ in 0..5 {
+            trie.remove("prof");
+        }
+        assert_eq!(
+            trie.complete::<String>("pro", 2),
+            ["providences", "province"]
+        );
+        trie.retain(|key, _, _| key.len() != 11);
+        assert_eq!(trie.complete::<String>("pro", 2), ["province", "professor"]);
+        assert_eq!(trie.root.best, 4);
+        trie.clear();
+        assert!(trie.complete::<String>("", 3).is_empty());
+    }
+
+    #[test]
+    fn complete_matches_scan() {
+        // a small alphabet makes long shared prefixes and many ties
+        let mut seed = 7u64;
+        let mut next = move |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+        let mut trie: Trie<char, ()> = Trie::new();
+        for _ in 0..2000 {
+            let len = 1 + next(5) as usize;
+            let word: String = (0..len).map(|_| (b'a' + next(3) as u8) as char).collect();
+            if next(4) == 0 {
+                trie.remove(word.as_str());
+            } else if next(2) == 0 {
+                *trie.entry(word.as_str()).or_default() = ();
+            } else {
+                trie.insert(word.as_str(), ());
+            }
+        }
+        for prefix in ["", "a", "ab", "cc", "bab"] {
+            for k in [1, 3, 10, 1000] {
+                assert_eq!(
+                    trie.complete::<String>(prefix, k),
+                    complete_by_scan(&trie, prefix, k),
+                    "{prefix} {k}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fuzzy_finds_typos() {
+        let mut trie: Trie<char, ()> = Trie::new();
+        for word in [
+            "potatoe",
+            "province",
+            "profile",
+            "profileur",
+            "profiteur",
+            "prof",
+            "professor",
+            "provinces",
+            "providence",
+            "providences",
+        ] {
+            trie.insert(word, ());
+        }
+        trie.insert("province", ());
+
+        // two letters swapped are two substitutions
+        assert!(trie.search_fuzzy::<String>("proffesor", 1).is_empty());
+        assert_eq!(
+            trie.search_fuzzy::<String>("proffesor", 2),
+            [("professor".to_string(), 2)]
+        );
+        assert_eq!(
+            trie.search_fuzzy::<String>("provinse", 2),
+            [("province".to_string(), 1), ("provinces".to_string(), 2)]
+        );
+        assert_eq!(
+            trie.search_fuzzy::<String>("profile", 2),
+            [("profile".to_string(), 0), ("profileur".to_string(), 2),]
+        );
+        assert_eq!(
+            trie.search_fuzzy::<String>("prof", 0),
+            [("prof".to_string(), 0)]
+        );
+        assert!(trie.search_fuzzy::<String>("xyz", 2).is_empty());
+
+        // a mistyped prefix still completes, the more inserted first
+        assert_eq!(
+            trie.complete_fuzzy::<String>("prvi", 1, 3),
+            [
+                ("province".to_string(), 1),
+                ("providence".to_string(), 1),
+                ("providences".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            trie.complete_fuzzy::<String>("potat", 1, 10),
+            [("potatoe".to_string(), 0)]
+        );
+        assert_eq!(
+            trie.complete_fuzzy::<String>("proffe", 1, 10)[0].0,
+            "professor"
+        );
+    }
+
+    #[test]
+    fn fuzzy_matches_scan() {
+        fn levenshtein(a: &[char], b: &[char]) -> usize {
+            let mut row: Vec<usize> = (0..=b.len()).collect();
+            for (i, x) in a.iter().enumerate() {
+                let mut prev = row[0];
+                row[0] = i + 1;
+                for (j, y) in b.iter().enumerate() {
+                    let cur = (prev + usize::from(x != y))
+                        .min(row[j] + 1)
+                        .min(row[j + 1] + 1);
+                    prev = row[j + 1];
+                    row[j + 1] = cur;
+                }
+            }
+            row[b.len()]
+        }
+        let words = [
+            "", "a", "ab", "abc", "abd", "bcd", "bad", "cab", "abcde", "dcba", "aab",
+        ];
+        let mut trie: Trie<char, ()> = Trie::new();
+        for w in words {
+            trie.insert(w, ());
+        }
+        for query in ["", "a", "ab", "ba", "abd", "xabc", "edcba"] {
+            let q: Vec<char> = query.chars().collect();
+            for max in 0..4 {
+                let mut fuzzy: Vec<(String, usize)> = trie.search_fuzzy(query, max);
+                fuzzy.sort();
+                let mut scan: Vec<(String, usize)> = words
+                    .iter()
+                    .map(|w| {
+                        (
+                            w.to_string(),
+                            levenshtein(&w.chars().collect::<Vec<_>>(), &q),
+                        )
+                    })
+                    .filter(|(_, d)| *d <= max)
+                    .collect();
+                scan.sort();
+                assert_eq!(fuzzy, scan, "{query} {max}");
+
+                // prefix mode: the closest of the word's prefixes
+                let mut fuzzy: Vec<(String, usize)> = trie.complete_fuzzy(query, max, usize::MAX);
+                fuzzy.sort();
+                let mut scan: Vec<(String, usize)> = words
+                    .iter()
+                    .map(|w| {
+                        let w: Vec<char> = w.chars().collect();
+                        let d = (0..=w.len())
+                            .map(|i| levenshtein(&w[..i], &q))
+                            .min()
+                            .unwrap();
+                        (w.iter().collect(), d)
+                    })
+                    .filter(|(_, d)| *d <= max)
+                    .collect();
+                scan.sort();
+                assert_eq!(fuzzy, scan, "{query} {max} prefix");
+            }
+        }
+    }
+
+    #[test]
+    fn iterates_in_order() {
+        let words = [
+            "potatoe",
+            "province",
+            "profile",
+            "profileur",
+            "profiteur",
+            "prof",
+            "professor",
+            "provinces",
+            "providence",
+            "providences",
+        ];
+        let mut trie: Trie<char, ()> = words.into_iter().collect();
+        trie.extend(["prof", "pro"]);
+        let mut sorted: Vec<&str> = words.to_vec();
+        sorted.push("pro");
+        sorted.sort();
+        let keys = |it: &mut dyn Iterator<Item = Vec<char>>| -> Vec<String> {
+            it.map(|k| k.into_iter().collect()).collect()
+        };
+        assert_eq!(keys(&mut trie.keys()), sorted);
+        assert_eq!(trie.exists("prof").map(|n| n.count), Some(2));
+        assert_eq!((&trie).into_iter().count(), sorted.len());
+
+        assert_eq!(
+            keys(&mut trie.iter_prefix("provi").map(|(k, _)| k)),
+            ["providence", "providences", "province", "provinces"]
+        );
+        assert_eq!(
+            keys(&mut trie.iter_prefix("prof").map(|(k, _)| k)),
+            ["prof", "professor", "profile", "profileur", "profiteur"]
+        );
+        assert_eq!(trie.iter_prefix("pz").count(), 0);
+        assert_eq!(trie.iter_prefix("").count(), sorted.len());
+
+        // ranges against filtering the sorted list
+        let check = |got: Vec<String>, keep: &dyn Fn(&str) -> bool| {
+            let want: Vec<&str> = sorted.iter().copied().filter(|s| keep(s)).collect();
+            assert_eq!(got, want);
+        };
+        let range =
+            |r: iter::Range<'_, char, ()>| r.map(|(k, _)| k.into_iter().collect()).collect();
+        check(range(trie.range("prof".."provi")), &|s| {
+            ("prof".."provi").contains(&s)
+        });
+        check(range(trie.range("prof"..="providence")), &|s| {
+            ("prof"..="providence").contains(&s)
+        });
+        check(range(trie.range("profa"..)), &|s| s >= "profa");
+        check(range(trie.range(.."pro")), &|s| s < "pro");
+        check(range(trie.range::<&str>(..)), &|_| true);
+        check(range(trie.range("q".."z")), &|_| false);
+        check(
+            range(trie.range::<&str>((
+                std::ops::Bound::Excluded("prof"),
+                std::ops::Bound::Unbounded,
+            ))),
+            &|s| s > "prof",
+        );
+
+        // owned, with values
+        let mut lengths: Trie<u8, usize> = Trie::new();
+        for w in words {
+            lengths.insert(w, w.len());
+        }
+        assert_eq!(lengths.values().copied().max(), Some(11));
+        let owned: Vec<(Vec<u8>, usize)> = lengths.into_iter().collect();
+        assert_eq!(owned.len(), words.len());
+        assert!(owned.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(owned.iter().all(|(k, n)| k.len() == *n));
+
+        let mut empty: Trie<char, ()> = Trie::new();
+        assert_eq!(empty.iter().count(), 0);
+        empty.insert("", ());
+        assert_eq!(empty.into_iter().collect::<Vec<_>>(), [(vec![], ())]);
+    }
+
+    #[test]
+    fn saves_and_loads() -> Result<(), Error> {
+        let mut trie: Trie<char, usize> = Trie::new();
+        for w in bench::words(2_000, 7) {
+            trie.insert(w.as_str(), w.len());
+        }
+        trie.insert("prof", 4);
+        trie.insert("prof", 4);
+        trie.remove(bench::words(1, 7)[0].as_str());
+        let same = |other: &Trie<char, usize>| {
+            assert_eq!(other.to_string(), trie.to_string());
+            for p in ["", "b", "pro", "stra", "zz"] {
+                assert_eq!(other.search::<String>(p), trie.search::<String>(p));
+                assert_eq!(
+                    other.complete::<String>(p, 5),
+                    trie.complete::<String>(p, 5)
+                );
+            }
+            assert!(other.iter().eq(trie.iter()));
+        };
+
+        // serde, as derived
+        let derived = bincode::serialize(&trie)?;
+        same(&bincode::deserialize(&derived)?);
+
+        // flattened
+        let mut saved = Vec::new();
+        trie.save(&mut saved)?;
+        same(&Trie::load(&saved[..])?);
+        assert!(saved.len() < derived.len() / 2);
+
+        let empty: Trie<char, usize> = Trie::new();
+        saved.clear();
+        empty.save(&mut saved)?;
+        assert_eq!(Trie::<char, usize>::load(&saved[..])?.iter().count(), 0);
+
+        // a trie cut short, with nodes shuffled, claiming more children
+        // than there are nodes, or with a value its count doesn't match
+        saved.clear();
+        trie.save(&mut saved)?;
+        assert!(Trie::<char, usize>::load(&saved[..saved.len() / 2]).is_err());
+        let mut compact = trie.compact();
+        compact.nodes.swap(1, 2);
+        let swapped = Compact {
+            labels: compact.labels,
+            nodes: compact.nodes,
+            vals: compact.vals.into_iter().copied().collect(),
+        };
+        assert!(matches!(Trie::try_from(swapped), Err(Error::Corrupt(_))));
+        let mut compact = trie.compact();
+        compact.nodes[0].children = u32::MAX;
+        saved.clear();
+        bincode::DefaultOptions::new().serialize_into(&mut saved, &compact)?;
+        assert!(matches!(
+            Trie::<char, usize>::load(&saved[..]),
+            Err(Error::Corrupt(_))
+        ));
+        let compact = trie.compact();
+        let i = compact.nodes.iter().position(|f| f.count == 0).unwrap();
+        for (count, val) in [(0, true), (1, false)] {
+            let mut nodes = compact.nodes.clone();
+            nodes[i].count = count;
+            nodes[i].val = val;
+            let mismatched = Compact {
+                labels: compact.labels.clone(),
+                nodes,
+                vals: compact.vals.iter().copied().copied().collect(),
+            };
+            assert!(matches!(Trie::try_from(mismatched), Err(Error::Corrupt(_))));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn frozen_matches_trie() {
+        let words = bench::words(5_000, 3);
+        let build = || {
+            let mut trie: Trie<char, usize> = Trie::new();
+            for (i, w) in words.iter().enumerate() {
+                trie.insert(w.as_str(), i);
+            }
+            trie.insert("pro", 1);
+            trie.insert("pro", 2);
+            trie.insert("", 0);
+            trie
+        };
+        let trie = build();
+        let frozen = build().freeze();
+        assert_eq!(frozen.nodes(), trie.nodes());
+        assert!(frozen.memory() * 5 < trie.memory());
+        for w in words
+            .iter()
+            .map(String::as_str)
+            .chain(["", "pro", "pr", "x"])
+        {
+            assert_eq!(frozen.exists(w), trie.exists(w).map(|n| n.count), "{w}");
+            assert_eq!(frozen.get(w), trie.get(w));
+            let miss = format!("{w}q");
+            assert_eq!(frozen.exists(miss.as_str()), None);
+        }
+        for w in words.iter().step_by(50) {
+            let p = &w[..w.len().min(2)];
+            assert_eq!(frozen.search::<String>(p), trie.search::<String>(p));
+            assert_eq!(
+                frozen.complete::<String>(p, 7),
+                trie.complete::<String>(p, 7)
+            );
+        }
+
+        let empty: Trie<u8, ()> = Trie::new();
+        let empty = empty.freeze();
+        assert_eq!(empty.exists(""), None);
+        assert!(empty.search::<Vec<u8>>("").is_empty());
+    }
+
+    #[test]
+    fn shared_under_load() {
+        use shared::SharedTrie;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        fn shareable<T: Send + Sync>(_: &T) {}
+        let words = bench::words(800, 11);
+        let (kept, churned) = words.split_at(400);
+        let initial: Trie<char, usize> = kept.iter().map(String::as_str).collect();
+        let shared = SharedTrie::from(initial);
+        shareable(&shared);
+
+        // each write adds or takes a word together with a tagged copy, so
+        // a consistent view holds both or neither
+        let writing = AtomicBool::new(true);
+        thread::scope(|s| {
+            let writers: Vec<_> = churned
+                .chunks(churned.len() / 2)
+                .map(|chunk| {
+                    let shared = &shared;
+                    s.spawn(move || {
+                        for w in chunk {
+                            shared.update(|t| {
+                                t.insert(w.as_str(), 1);
+                                t.insert(format!("#{w}").as_str(), 1);
+                            });
+                        }
+                        for w in chunk.iter().step_by(2) {
+                            let both = shared.update(|t| {
+                                t.remove(w.as_str()) && t.remove(format!("#{w}").as_str())
+                            });
+                            assert!(both);
+                        }
+                    })
+                })
+                .collect();
+            for _ in 0..3 {
+                s.spawn(|| {
+                    let mut reads = 0;
+                    while writing.load(Ordering::Relaxed) || reads == 0 {
+                        let snap = shared.snapshot();
+                        for tagged in snap.search::<String>("#") {
+                            assert!(snap.exists(&tagged[1..]).is_some(), "{tagged}");
+                        }
+                        for w in kept.iter().step_by(97) {
+                            assert_eq!(shared.exists(w.as_str()), Some(1));
+                        }
+                        assert_eq!(shared.complete::<String>("", 3).len(), 3);
+                        reads += 1;
+                    }
+                });
+            }
+            for w in writers {
+                w.join().expect("writer");
+            }
+            writing.store(false, Ordering::Relaxed);
+        });
+
+        let last = shared.snapshot();
+        for (i, w) in churned.iter().enumerate() {
+            // the chunks start at even indices, so the even ones went
+            let present = i % 2 == 1;
+            assert_eq!(last.exists(w.as_str()).is_some(), present, "{w}");
+            assert_eq!(shared.get(format!("#{w}").as_str()).is_some(), present);
+        }
+        assert_eq!(last.iter().count(), kept.len() + churned.len());
+    }
+
+    #[test]
+    fn shared_publishes_batches() {
+        use shared::SharedTrie;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        const BATCH: usize = 100;
+        let shared: SharedTrie<char, usize> = SharedTrie::new();
+        let writing = AtomicBool::new(true);
+        thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| {
+                    let mut reads = 0;
+                    while writing.load(Ordering::Relaxed) || reads == 0 {
+                        // one round's keys, all of them, or none at all
+                        let snap = shared.snapshot();
+                        let rounds: Vec<usize> = snap.values().copied().collect();
+                        assert!(rounds.is_empty() || rounds.len() == BATCH);
+                        assert!(rounds.iter().all(|&r| r == rounds[0]));
+                        reads += 1;
+                    }
+                });
+            }
+            // each round swaps the last round's keys for its own
+            for round in 1..=50 {
+                shared.update(|t| {
+                    for i in 0..BATCH {
+                        t.remove(format!("{}/{i}", round - 1).as_str());
+                        t.insert(format!("{round}/{i}").as_str(), round);
+                    }
+                });
+            }
+            writing.store(false, Ordering::Relaxed);
+        });
+        assert_eq!(shared.exists("50/0"), Some(1));
+        assert_eq!(shared.exists("49/0"), None);
+    }
+
+    #[test]
+    fn longest_prefix() {
+        let mut routes: Trie<&str, &str> = Trie::new();
+        routes.insert(["api"], "api");
+        routes.insert(["api", "users"], "users");
+        routes.insert(["api", "users", "me"], "me");
+        fn path(p: &str) -> Vec<&str> {
+            p.split('/').collect()
+        }
+        assert_eq!(
+            routes.longest_prefix_of(path("api/users/42")),
+            Some((2, &"users"))
+        );
+        assert_eq!(
+            routes.longest_prefix_of(path("api/users/me")),
+            Some((3, &"me"))
+        );
+        assert_eq!(
+            routes.longest_prefix_of(path("api/orders")),
+            Some((1, &"api"))
+        );
+        assert_eq!(routes.longest_prefix_of(path("www")), None);
+        routes.insert(Vec::<&str>::new(), "root");
+        assert_eq!(routes.longest_prefix_of(path("www")), Some((0, &"root")));
+
+        // greedy tokenizing
+        let vocab: Trie<char, ()> = ["un", "under", "stand", "st", "a", "and", "ing"]
+            .into_iter()
+            .collect();
+        let mut text: Vec<char> = "understanding".chars().collect();
+        let mut tokens = Vec::new();
+        while let Some((n, _)) = vocab.longest_prefix_of(&text[..]) {
+            tokens.push(text.drain(..n).collect::<String>());
+        }
+        assert_eq!(tokens, ["under", "stand", "ing"]);
+        assert!(text.is_empty());
+    }
+
+    #[test]
+    fn aho_corasick_finds_all() {
+        let mut trie: Trie<char, usize> = Trie::new();
+        for (i, p) in ["he", "she", "his", "hers", "h", "e"]
+            .into_iter()
+            .enumerate()
+        {
+            trie.insert(p, i);
+        }
+        let ac = trie.automaton();
+        let found: Vec<(usize, usize, usize)> = ac
+            .find_iter("ushers")
+            .map(|m| (m.start, m.end, *m.val))
+            .collect();
+        assert_eq!(
+            found,
+            [(2, 3, 4), (1, 4, 1), (2, 4, 0), (3, 4, 5), (2, 6, 3)]
+        );
+
+        // against trying every pattern at every position
+        let patterns = bench::words(300, 5);
+        let mut trie: Trie<u8, usize> = Trie::new();
+        for (i, p) in patterns.iter().enumerate() {
+            let short = &p[..p.len().min(4)];
+            trie.insert(short, i);
+        }
+        trie.insert("", 0);
+        let ac = trie.automaton();
+        let text = bench::words(2_000, 6).concat();
+        let text = text.as_bytes();
+        let mut found: Vec<(usize, usize)> = ac.find_iter(text).map(|m| (m.start, m.end)).collect();
+        let mut want = Vec::new();
+        for end in 1..=text.len() {
+            // the patterns are at most four long
+            for start in end.saturating_sub(4)..end {
+                if trie.exists(&text[start..end]).is_some() {
+                    want.push((start, end));
+                }
+            }
+        }
+        found.sort();
+        want.sort();
+        assert!(!want.is_empty());
+        assert_eq!(found, want);
+        assert!(ac
+            .stream(text.iter().copied())
+            .map(|m| m.end)
+            .eq(ac.find_iter(text).map(|m| m.end)));
+    }
+
+    #[test]
+    fn normalizes_keys() {
+        use normalize::{Form, NormalizedTrie, Normalizer};
+
+        let nfd = "Cafe\u{301}";
+        assert_ne!(nfd, "Café");
+        let mut raw: NormalizedTrie<()> = NormalizedTrie::new(Normalizer {
+            form: Form::Raw,
+            ..Default::default()
+        });
+        raw.insert("Café", ());
+        assert_eq!(raw.exists(nfd), None);
+
+        let mut nfc: NormalizedTrie<()> = NormalizedTrie::default();
+        nfc.insert("Café", ());
+        nfc.insert(nfd, ());
+        assert_eq!(nfc.exists("Café"), Some(2));
+        assert_eq!(nfc.exists("café"), None);
+        assert_eq!(nfc.trie().iter().count(), 1);
+
+        let mut folded: NormalizedTrie<usize> = NormalizedTrie::new(Normalizer {
+            fold_case: true,
+            ..Default::default()
+        });
+        folded.insert("Prof", 1);
+        folded.insert("prof", 2);
+        folded.insert("Professor", 3);
+        folded.insert("Straße", 4);
+        assert_eq!(folded.exists("PROF"), Some(2));
+        assert_eq!(folded.get("pRoF"), Some(&2));
+        assert_eq!(folded.search("PRO"), ["professor", "prof"]);
+        assert_eq!(folded.complete("Pro", 1), ["prof"]);
+        assert_eq!(folded.get("STRASSE"), Some(&4));
+        assert_eq!(folded.search_fuzzy("PROFF", 1), [("prof".to_string(), 1)]);
+
+        let stripped = Normalizer {
+            form: Form::Nfc,
+            fold_case: true,
+            strip_accents: true,
+        };
+        assert_eq!(stripped.apply("Crème Brûlée"), "creme brulee");
+        assert_eq!(stripped.apply("Cafe\u{301}"), "cafe");
+        // Hangul decomposes into letters, not marks, and comes back whole
+        assert_eq!(stripped.apply("한국"), "한국");
+        let mut t = NormalizedTrie::new(stripped);
+        t.insert("Crème Brûlée", ());
+        assert_eq!(t.exists("creme brulee"), Some(1));
+        assert!(t.remove("CRÈME BRULÉE"));
+        assert_eq!(t.trie().iter().count(), 0);
+
+        let nfkc = Normalizer {
+            form: Form::Nfkc,
+            ..Default::default()
+        };
+        assert_eq!(nfkc.apply("ﬁle Ｐｒｏｆ"), "file Prof");
+        assert_eq!(Normalizer::default().apply("ﬁle"), "ﬁle");
+        assert_eq!(
+            Normalizer {
+                form: Form::Raw,
+                fold_case: true,
+                strip_accents: false,
+            }
+            .apply(nfd),
+            "cafe\u{301}"
+        );
+    }
+}
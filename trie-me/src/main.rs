@@ -1,163 +1,533 @@
-use std::collections::VecDeque;
-use std::fmt::Display;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use node::Node;
+use clap::{Parser, Subcommand};
+use trie_me::{
+    bench::{self, Report},
+    normalize::{Form, Normalizer},
+    Trie,
+};
 
-#[derive(Debug)]
-pub enum Error {}
+/// Load words into a trie and query it.
+#[derive(Debug, Parser)]
+struct Cli {
+    /// word lists, split on whitespace; a word seen twice counts twice.
+    /// Read from stdin when neither this nor --trie is given
+    #[arg(long, short, global = true)]
+    words: Vec<PathBuf>,
+    /// a trie written by `dump --save`, loaded before the word lists
+    #[arg(long, short, global = true)]
+    trie: Option<PathBuf>,
+    /// Unicode normalization of the words and queries; a saved trie keeps
+    /// whatever it was saved with
+    #[arg(long, value_enum, global = true, default_value_t = Form::Nfc)]
+    form: Form,
+    /// match regardless of case
+    #[arg(long, global = true)]
+    fold_case: bool,
+    /// match regardless of accents
+    #[arg(long, global = true)]
+    strip_accents: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Query(Query),
+    /// Complete prefixes read line by line; `:help` lists the commands.
+    Repl {
+        #[arg(short, default_value_t = 10)]
+        k: usize,
+    },
+    /// Count where the words occur in texts, one text per line or per CSV
+    /// record, in a single pass over them.
+    Scan {
+        /// read from stdin when absent
+        texts: Vec<PathBuf>,
+        /// read the texts as CSV with headers, from this column
+        #[arg(long)]
+        column: Option<String>,
+    },
+    /// Compare the trie layouts on the words, or on `n` generated ones.
+    Bench {
+        #[arg(long, default_value_t = 100_000)]
+        n: usize,
+    },
+}
+
+/// The commands that only look the trie up.
+#[derive(Debug, Subcommand)]
+enum Query {
+    /// The most inserted words starting with a prefix.
+    Complete {
+        prefix: String,
+        #[arg(short, default_value_t = 10)]
+        k: usize,
+    },
+    /// Whether each word was loaded, and how many times.
+    Exists {
+        #[arg(required = true, value_name = "WORD")]
+        queries: Vec<String>,
+    },
+    /// The words within an edit distance of a query, closest first.
+    Fuzzy {
+        query: String,
+        #[arg(long, default_value_t = 1)]
+        max: usize,
+        /// match the query against word prefixes, completing them
+        #[arg(long)]
+        prefix: bool,
+        #[arg(short, default_value_t = 10)]
+        k: usize,
+    },
+    /// The distinct words starting with a prefix, and their insertions.
+    Count {
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+    /// Size of the trie and how long it took to load.
+    Stats,
+    /// Every word starting with a prefix, in order, with its count; or,
+    /// with --save, the whole trie in its compact form.
+    Dump {
+        #[arg(default_value = "")]
+        prefix: String,
+        #[arg(long, conflicts_with = "prefix")]
+        save: Option<PathBuf>,
+    },
+}
 
-mod node {
-    #[derive(Debug, Default)]
-    pub struct Node {
-        pub children: Vec<Node>,
-        pub key: Option<char>,
-        pub val: Option<String>,
-        pub count: usize,
+impl Cli {
+    fn normalizer(&self) -> Normalizer {
+        Normalizer {
+            form: self.form,
+            fold_case: self.fold_case,
+            strip_accents: self.strip_accents,
+        }
     }
+}
 
-    impl Node {
-        pub fn new() -> Self {
-            Node {
-                ..Default::default()
-            }
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+type Words = Trie<char, ()>;
+
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    let norm = cli.normalizer();
+    // the trie, and how long it took to load
+    let loaded = || -> Result<(Words, Duration), Error> {
+        let took = Instant::now();
+        let trie = load(&cli, &norm)?;
+        Ok((trie, took.elapsed()))
+    };
+    let out = &mut BufWriter::new(io::stdout().lock());
+    match &cli.command {
+        Command::Query(query) => {
+            let (trie, took) = loaded()?;
+            run(&trie, normalize_args(query, &norm), took, out)?;
         }
-        pub fn with_key(c: char) -> Self {
-            Node {
-                key: Some(c),
-                ..Default::default()
+        Command::Repl { k } => {
+            let (trie, _) = loaded()?;
+            repl(&trie, &norm, *k, io::stdin().lock(), out)?;
+        }
+        Command::Scan { texts, column } => {
+            let (trie, _) = loaded()?;
+            let readers: Vec<Box<dyn io::Read>> = match texts.is_empty() {
+                true => vec![Box::new(io::stdin().lock())],
+                false => texts
+                    .iter()
+                    .map(|p| Ok(Box::new(File::open(p)?) as Box<dyn io::Read>))
+                    .collect::<Result<_, Error>>()?,
+            };
+            scan(&trie, &norm, readers, column.as_deref(), out)?;
+        }
+        Command::Bench { n } => {
+            let words = match cli.words.is_empty() {
+                true => bench::words(*n, 42),
+                false => read_words(&cli.words)?,
+            };
+            writeln!(out, "{} words", words.len())?;
+            writeln!(out, "{}", Report::HEADER)?;
+            for r in bench::run(&words) {
+                writeln!(out, "{r}")?;
             }
         }
     }
+    Ok(())
 }
 
-#[derive(Debug, Default)]
-pub struct Trie {
-    pub root: Node,
-}
-
-impl Trie {
-    pub fn new() -> Self {
-        Trie {
-            ..Default::default()
+/// Answers a query, its arguments already normalized.
+fn run(trie: &Words, query: Query, took: Duration, out: &mut impl Write) -> Result<(), Error> {
+    match query {
+        Query::Complete { prefix, k } => {
+            for w in trie.complete::<String>(prefix.as_str(), k) {
+                writeln!(out, "{w}\t{}", count(trie, &w))?;
+            }
         }
-    }
-    pub fn insert(&mut self, s: &str) {
-        let mut cur = &mut self.root;
-        for c in s.chars() {
-            match cur.children.binary_search_by(|n| n.key.cmp(&Some(c))) {
-                Ok(i) => {
-                    cur = &mut cur.children[i];
-                }
-                Err(i) => {
-                    cur.children.insert(i, Node::with_key(c));
-                    cur = &mut cur.children[i];
+        Query::Exists { queries } => {
+            for w in queries {
+                writeln!(out, "{w}\t{}", count(trie, &w))?;
+            }
+        }
+        Query::Fuzzy {
+            query,
+            max,
+            prefix,
+            k,
+        } => {
+            let found = match prefix {
+                true => trie.complete_fuzzy::<String>(query.as_str(), max, k),
+                false => trie.search_fuzzy::<String>(query.as_str(), max),
+            };
+            for (w, d) in found.into_iter().take(k) {
+                writeln!(out, "{w}\t{d}")?;
+            }
+        }
+        Query::Count { prefix } => {
+            let (distinct, total) = tally(trie, &prefix);
+            writeln!(out, "{distinct} distinct, {total} inserted")?;
+        }
+        Query::Stats => {
+            let (distinct, total) = tally(trie, "");
+            let longest = trie.keys().map(|k| k.len()).max().unwrap_or(0);
+            writeln!(out, "words     {total}")?;
+            writeln!(out, "distinct  {distinct}")?;
+            writeln!(out, "longest   {longest}")?;
+            writeln!(out, "nodes     {}", trie.nodes())?;
+            writeln!(out, "bytes     {}", trie.memory())?;
+            writeln!(out, "loaded in {took:.1?}")?;
+        }
+        Query::Dump { prefix, save } => match save {
+            Some(path) => trie.save(BufWriter::new(File::create(path)?))?,
+            None => {
+                for (key, _) in trie.iter_prefix(prefix.as_str()) {
+                    let w: String = key.iter().collect();
+                    writeln!(out, "{w}\t{}", count(trie, &w))?;
                 }
             }
+        },
+    }
+    Ok(())
+}
+
+/// The words of each file, in order.
+fn read_words(paths: &[PathBuf]) -> Result<Vec<String>, Error> {
+    let mut words = Vec::new();
+    for path in paths {
+        for line in BufReader::new(File::open(path)?).lines() {
+            words.extend(line?.split_whitespace().map(String::from));
         }
+    }
+    Ok(words)
+}
 
-        cur.count += 1;
-        cur.val.replace(s.to_string());
+/// The query with the words and prefixes it looks up normalized like the
+/// trie's keys.
+fn normalize_args(query: &Query, norm: &Normalizer) -> Query {
+    let n = |s: &String| norm.apply(s);
+    match query {
+        Query::Complete { prefix, k } => Query::Complete {
+            prefix: n(prefix),
+            k: *k,
+        },
+        Query::Exists { queries } => Query::Exists {
+            queries: queries.iter().map(n).collect(),
+        },
+        Query::Fuzzy {
+            query,
+            max,
+            prefix,
+            k,
+        } => Query::Fuzzy {
+            query: n(query),
+            max: *max,
+            prefix: *prefix,
+            k: *k,
+        },
+        Query::Count { prefix } => Query::Count { prefix: n(prefix) },
+        Query::Dump { prefix, save } => Query::Dump {
+            prefix: n(prefix),
+            save: save.clone(),
+        },
+        Query::Stats => Query::Stats,
     }
-    pub fn exists<'a>(&'a self, s: &str) -> Option<&'a Node> {
-        let mut cur = &self.root;
-        for c in s.chars() {
-            match cur.children.binary_search_by(|n| n.key.cmp(&Some(c))) {
-                Ok(i) => {
-                    cur = &cur.children[i];
-                }
-                Err(_) => {
-                    return None;
-                }
-            }
+}
+
+fn load(cli: &Cli, norm: &Normalizer) -> Result<Words, Error> {
+    let mut trie = match &cli.trie {
+        Some(path) => Trie::load(BufReader::new(File::open(path)?))?,
+        None => Trie::new(),
+    };
+    for path in &cli.words {
+        insert_words(&mut trie, norm, BufReader::new(File::open(path)?))?;
+    }
+    if cli.words.is_empty() && cli.trie.is_none() {
+        let takes_stdin = match &cli.command {
+            Command::Repl { .. } => true,
+            Command::Scan { texts, .. } => texts.is_empty(),
+            _ => false,
+        };
+        if takes_stdin {
+            return Err("stdin is taken, so give --words or --trie".into());
         }
+        insert_words(&mut trie, norm, io::stdin().lock())?;
+    }
+    Ok(trie)
+}
 
-        if cur.count > 0 {
-            Some(cur)
-        } else {
-            None
+/// Inserts the whitespace-separated words of `r`, normalized.
+fn insert_words(trie: &mut Words, norm: &Normalizer, r: impl BufRead) -> Result<(), Error> {
+    for line in r.lines() {
+        for w in line?.split_whitespace() {
+            trie.insert(norm.apply(w).as_str(), ());
         }
     }
-    pub fn search(&self, s: &str) -> Vec<String> {
-        let mut cur = &self.root;
-        for c in s.chars() {
-            match cur.children.binary_search_by(|n| n.key.cmp(&Some(c))) {
-                Ok(i) => {
-                    cur = &cur.children[i];
-                }
-                Err(_) => return Default::default(),
+    Ok(())
+}
+
+/// Times `word` was inserted.
+fn count(trie: &Words, word: &str) -> usize {
+    trie.exists(word).map_or(0, |n| n.count)
+}
+
+/// Distinct words starting with `prefix`, and their total insertions.
+fn tally(trie: &Words, prefix: &str) -> (usize, usize) {
+    trie.iter_prefix(prefix)
+        .map(|(key, _)| trie.exists(&key[..]).map_or(0, |n| n.count))
+        .fold((0, 0), |(distinct, total), c| (distinct + 1, total + c))
+}
+
+/// Prints each word found in the texts, with its matches and the texts
+/// it is in, most matched first.
+fn scan(
+    trie: &Words,
+    norm: &Normalizer,
+    readers: Vec<Box<dyn io::Read>>,
+    column: Option<&str>,
+    out: &mut impl Write,
+) -> Result<(), Error> {
+    let words: Vec<String> = trie.keys().map(|k| k.into_iter().collect()).collect();
+    let mut ids: Trie<char, usize> = Trie::new();
+    for (i, w) in words.iter().enumerate() {
+        ids.insert(w.as_str(), i);
+    }
+    let ac = ids.automaton();
+    // matches, texts, and the last text seen in, by word
+    let mut found = vec![(0, 0, usize::MAX); words.len()];
+    let mut text = 0;
+    let mut tally = |t: &str| {
+        for m in ac.find_iter(norm.apply(t).as_str()) {
+            let f = &mut found[*m.val];
+            f.0 += 1;
+            if f.2 != text {
+                f.1 += 1;
+                f.2 = text;
             }
         }
-        let mut results = Vec::new();
-        let mut q = VecDeque::new();
-        q.push_front(cur);
-        while let Some(n) = q.pop_front() {
-            for child in &n.children {
-                q.push_front(child);
+        text += 1;
+    };
+    for r in readers {
+        match column {
+            Some(column) => {
+                let mut csv = csv::Reader::from_reader(r);
+                let i = csv
+                    .headers()?
+                    .iter()
+                    .position(|h| h == column)
+                    .ok_or_else(|| format!("no column {column}"))?;
+                for record in csv.records() {
+                    tally(record?.get(i).unwrap_or(""));
+                }
             }
-            if n.count > 0 {
-                results.push((n.count, n.val.as_ref()));
+            None => {
+                for line in BufReader::new(r).lines() {
+                    tally(&line?);
+                }
             }
         }
-        results.sort_by(|a, b| a.cmp(b));
-        results.iter().map(|(_, b)| b.unwrap().clone()).collect()
     }
+
+    let mut rows: Vec<_> = words.iter().zip(found).filter(|(_, f)| f.0 > 0).collect();
+    rows.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(b.0)));
+    writeln!(out, "{text} texts")?;
+    for (w, (matches, texts, _)) in rows {
+        writeln!(out, "{w}\t{matches}\t{texts}")?;
+    }
+    Ok(())
 }
 
-impl Display for Trie {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut q = VecDeque::new();
-        q.push_back(&self.root);
-
-        while !q.is_empty() {
-            for _ in 0..q.len() {
-                if let Some(node) = q.pop_front() {
-                    for child in &node.children {
-                        let r = write!(f, "{} ", &child.key.unwrap());
-                        if r.is_err() {
-                            return r;
-                        }
-                        if child.children.len() > 0 {
-                            q.push_back(&child);
-                        }
+const REPL_HELP: &str = "\
+<prefix>          the most inserted words starting with it
+:exists <word>    how many times the word was inserted
+:fuzzy <word> [max]
+                  the words within `max` edits, 1 by default
+:count <prefix>   distinct words starting with it, and their insertions
+:help             this
+:quit             leave, as does end of input";
+
+fn repl(
+    trie: &Words,
+    norm: &Normalizer,
+    k: usize,
+    mut input: impl BufRead,
+    out: &mut impl Write,
+) -> Result<(), Error> {
+    let mut line = String::new();
+    loop {
+        write!(out, "> ")?;
+        out.flush()?;
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(out)?;
+            break;
+        }
+        let line = norm.apply(&line);
+        let mut args = line.split_whitespace();
+        match args.next() {
+            None => {}
+            Some(":quit" | ":q") => break,
+            Some(":help") => writeln!(out, "{REPL_HELP}")?,
+            Some(":exists") => {
+                for w in args {
+                    writeln!(out, "{w}\t{}", count(trie, w))?;
+                }
+            }
+            Some(":fuzzy") => {
+                let Some(w) = args.next() else {
+                    writeln!(out, "usage: :fuzzy <word> [max]")?;
+                    continue;
+                };
+                let max = match args.next().map_or(Ok(1), str::parse) {
+                    Ok(max) => max,
+                    Err(e) => {
+                        writeln!(out, "bad distance: {e}")?;
+                        continue;
                     }
+                };
+                for (w, d) in trie.search_fuzzy::<String>(w, max).into_iter().take(k) {
+                    writeln!(out, "{w}\t{d}")?;
                 }
             }
-
-            if q.len() > 0 {
-                let r = writeln!(f);
-                if r.is_err() {
-                    return r;
+            Some(":count") => {
+                let (distinct, total) = tally(trie, args.next().unwrap_or(""));
+                writeln!(out, "{distinct} distinct, {total} inserted")?;
+            }
+            Some(cmd) if cmd.starts_with(':') => {
+                writeln!(out, "unknown command {cmd}; try :help")?;
+            }
+            Some(prefix) => {
+                let words = trie.complete::<String>(prefix, k);
+                if words.is_empty() {
+                    writeln!(out, "no words start with {prefix}")?;
+                }
+                for w in words {
+                    writeln!(out, "{w}\t{}", count(trie, &w))?;
                 }
             }
         }
-        Ok(())
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn it_works() -> Result<(), Error> {
+    const WORDS: &str = "pro prof prof profile\nprofit profit profit\nCafé cafe CAFE\n";
+
+    /// What `trie-me <args>` prints with `WORDS` loaded, answering `input`
+    /// if it is the REPL or scanning it if it is a scan.
+    fn answer(args: &[&str], input: &str) -> Result<String, Error> {
+        let cli = Cli::try_parse_from(["trie-me"].iter().chain(args))?;
+        let norm = cli.normalizer();
         let mut trie = Trie::new();
-        trie.insert("potatoe");
-        trie.insert("province");
-        trie.insert("profile");
-        trie.insert("profileur");
-        trie.insert("profiteur");
-        trie.insert("prof");
-        trie.insert("professor");
-        trie.insert("provinces");
-        trie.insert("providence");
-        trie.insert("providences");
-
-        dbg!(trie.search("prof"));
-        println!("{trie}");
+        insert_words(&mut trie, &norm, WORDS.as_bytes())?;
+        let mut out = Vec::new();
+        match cli.command {
+            Command::Query(query) => run(
+                &trie,
+                normalize_args(&query, &norm),
+                Duration::ZERO,
+                &mut out,
+            )?,
+            Command::Repl { k } => repl(&trie, &norm, k, input.as_bytes(), &mut out)?,
+            // the input is the one text, as CSV or as lines
+            Command::Scan { column, .. } => {
+                let texts: Vec<Box<dyn io::Read>> =
+                    vec![Box::new(io::Cursor::new(input.to_owned()))];
+                scan(&trie, &norm, texts, column.as_deref(), &mut out)?
+            }
+            Command::Bench { .. } => return Err("not a lookup".into()),
+        }
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn answers_commands() -> Result<(), Error> {
+        assert_eq!(
+            answer(&["complete", "pro", "-k", "2"], "")?,
+            "profit\t3\nprof\t2\n"
+        );
+        assert_eq!(answer(&["complete", "x"], "")?, "");
+        assert_eq!(
+            answer(&["exists", "prof", "profi", "cafe"], "")?,
+            "prof\t2\nprofi\t0\ncafe\t1\n"
+        );
+        assert_eq!(answer(&["count", "pro"], "")?, "4 distinct, 7 inserted\n");
+        assert_eq!(answer(&["count"], "")?, "7 distinct, 10 inserted\n");
+        assert_eq!(
+            answer(&["dump", "prof"], "")?,
+            "prof\t2\nprofile\t1\nprofit\t3\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn normalizes_queries() -> Result<(), Error> {
+        let folded = ["--fold-case", "--strip-accents"];
+        let exists = [&folded[..], &["exists", "CAFÉ", "Prof"]].concat();
+        assert_eq!(answer(&exists, "")?, "cafe\t3\nprof\t2\n");
+        let dump = [&folded[..], &["dump", "CA"]].concat();
+        assert_eq!(answer(&dump, "")?, "cafe\t3\n");
         Ok(())
     }
-}
 
-fn main() {
-    println!("Hello, world!");
+    #[test]
+    fn repl_reads_commands() -> Result<(), Error> {
+        let input = "prof\n:exists prof x\n:count pro\n\n:fuzzy\n:nope\n:q\nprof\n";
+        assert_eq!(
+            answer(&["repl", "-k", "2"], input)?,
+            "> profit\t3\nprof\t2\n\
+             > prof\t2\nx\t0\n\
+             > 4 distinct, 7 inserted\n\
+             > > usage: :fuzzy <word> [max]\n\
+             > unknown command :nope; try :help\n\
+             > "
+        );
+        // end of input ends it too
+        assert_eq!(answer(&["repl"], "x")?, "> no words start with x\n> \n");
+        Ok(())
+    }
+    #[test]
+    fn scans_csv_columns() -> Result<(), Error> {
+        // the id column holds words too, but only the text column is read
+        let csv = "id,text\nprof,\"profit, then prof\"\npro,Cafe\ncafe,nothing\n";
+        assert_eq!(
+            answer(&["--fold-case", "scan", "--column", "text"], csv)?,
+            "3 texts\npro\t2\t1\nprof\t2\t1\ncafe\t1\t1\nprofit\t1\t1\n"
+        );
+        assert!(answer(&["scan", "--column", "body"], csv).is_err());
+        // without a column every line is a text
+        assert_eq!(
+            answer(&["scan"], "prof\nx\n")?,
+            "2 texts\npro\t1\t1\nprof\t1\t1\n"
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,123 @@
+//! Text keys as readers see them rather than as they are encoded: "Café"
+//! typed precomposed and "Café" with a combining accent, or "Prof" and
+//! "prof", can be made the same key by normalizing both on the way in.
+
+use caseless::Caseless;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::Trie;
+
+/// A Unicode normalization form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum Form {
+    /// keys as given
+    Raw,
+    /// canonical composition: the same text however its accents are encoded
+    #[default]
+    Nfc,
+    /// compatibility composition: also ligatures, widths and the like
+    /// folded into their plain letters, "ﬁ" into "fi"
+    Nfkc,
+}
+
+/// How keys are normalized before they reach the trie.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Normalizer {
+    pub form: Form,
+    /// full Unicode case folding, so "Straße" matches "STRASSE"
+    pub fold_case: bool,
+    /// drops combining marks, so "crème brûlée" matches "creme brulee";
+    /// the rest is composed, even in the [`Form::Raw`] form
+    pub strip_accents: bool,
+}
+
+impl Normalizer {
+    pub fn apply(&self, text: &str) -> String {
+        if self.form == Form::Raw && !self.fold_case && !self.strip_accents {
+            return text.to_string();
+        }
+        // ASCII has no accents or compatibility forms, and folds to lower
+        if text.is_ascii() {
+            return match self.fold_case {
+                true => text.to_ascii_lowercase(),
+                false => text.to_string(),
+            };
+        }
+        // decomposed, marks can be dropped and cases folded one char at a
+        // time; composing again comes last, as folding can undo it
+        let chars: Box<dyn Iterator<Item = char>> = match self.form {
+            Form::Nfkc => Box::new(text.nfkd()),
+            Form::Nfc => Box::new(text.nfd()),
+            Form::Raw if self.strip_accents => Box::new(text.nfd()),
+            Form::Raw => Box::new(text.chars()),
+        };
+        let chars: Box<dyn Iterator<Item = char>> = match self.strip_accents {
+            true => Box::new(chars.filter(|&c| !is_combining_mark(c))),
+            false => chars,
+        };
+        let chars: Box<dyn Iterator<Item = char>> = match self.fold_case {
+            true => Box::new(chars.default_case_fold()),
+            false => chars,
+        };
+        match self.form {
+            // what stripping decomposed but left, such as Hangul, goes back
+            Form::Nfc | Form::Raw if self.strip_accents => chars.nfc().collect(),
+            Form::Raw => chars.collect(),
+            Form::Nfc => chars.nfc().collect(),
+            Form::Nfkc => chars.nfkc().collect(),
+        }
+    }
+}
+
+/// A [`Trie`] of text whose keys and queries all go through one
+/// [`Normalizer`]. Keys come back normalized.
+#[derive(Debug, Default)]
+pub struct NormalizedTrie<V> {
+    pub normalizer: Normalizer,
+    trie: Trie<char, V>,
+}
+
+impl<V> NormalizedTrie<V> {
+    pub fn new(normalizer: Normalizer) -> Self {
+        NormalizedTrie {
+            normalizer,
+            trie: Trie::new(),
+        }
+    }
+    /// The trie underneath, keyed by normalized text.
+    pub fn trie(&self) -> &Trie<char, V> {
+        &self.trie
+    }
+    pub fn insert(&mut self, key: &str, val: V) -> Option<V> {
+        self.trie.insert(self.normalizer.apply(key).as_str(), val)
+    }
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.trie.remove(self.normalizer.apply(key).as_str())
+    }
+    /// How many times `key`, or anything normalizing like it, was inserted.
+    pub fn exists(&self, key: &str) -> Option<usize> {
+        self.trie
+            .exists(self.normalizer.apply(key).as_str())
+            .map(|n| n.count)
+    }
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.trie.get(self.normalizer.apply(key).as_str())
+    }
+    pub fn search(&self, prefix: &str) -> Vec<String> {
+        self.trie.search(self.normalizer.apply(prefix).as_str())
+    }
+    pub fn complete(&self, prefix: &str, k: usize) -> Vec<String> {
+        self.trie
+            .complete(self.normalizer.apply(prefix).as_str(), k)
+    }
+    pub fn search_fuzzy(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.trie
+            .search_fuzzy(self.normalizer.apply(query).as_str(), max_edits)
+    }
+    pub fn complete_fuzzy(&self, prefix: &str, max_edits: usize, k: usize) -> Vec<(String, usize)> {
+        self.trie
+            .complete_fuzzy(self.normalizer.apply(prefix).as_str(), max_edits, k)
+    }
+}
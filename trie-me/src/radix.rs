@@ -0,0 +1,301 @@
+//! A radix (Patricia) trie: chains of single-child nodes are merged into
+//! one edge labelled with all their atoms, so long sparse keys take a node
+//! per branching point rather than per atom.
+
+use std::mem;
+
+use crate::Key;
+
+#[derive(Debug)]
+pub struct Node<K, V> {
+    /// the atoms on the edge into this node, empty only at the root
+    pub label: Vec<K>,
+    /// `label[0]`, kept inline so finding a child reads no labels
+    pub first: Option<K>,
+    /// ordered by the first atom of their label, which is unique
+    pub children: Vec<Node<K, V>>,
+    pub val: Option<V>,
+    /// times the key ending here was inserted
+    pub count: usize,
+}
+
+impl<K, V> Default for Node<K, V> {
+    fn default() -> Self {
+        Node {
+            label: Vec::new(),
+            first: None,
+            children: Vec::new(),
+            val: None,
+            count: 0,
+        }
+    }
+}
+
+impl<K: Ord, V> Node<K, V> {
+    /// Where the child whose label starts with `k` is, or would go.
+    fn find(&self, k: &K) -> Result<usize, usize> {
+        self.children
+            .binary_search_by(|n| n.first.as_ref().cmp(&Some(k)))
+    }
+}
+
+/// The [`Trie`](crate::Trie) API over a radix tree.
+#[derive(Debug)]
+pub struct RadixTrie<K, V> {
+    pub root: Node<K, V>,
+}
+
+impl<K, V> Default for RadixTrie<K, V> {
+    fn default() -> Self {
+        RadixTrie {
+            root: Node::default(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> RadixTrie<K, V> {
+    pub fn new() -> Self {
+        RadixTrie {
+            ..Default::default()
+        }
+    }
+    /// Stores `val` under `key`, returning the value it replaces.
+    pub fn insert(&mut self, key: impl Key<K>, val: V) -> Option<V> {
+        let key: Vec<K> = key.atoms().collect();
+        let mut cur = &mut self.root;
+        let mut rest = &key[..];
+        while let Some(k) = rest.first() {
+            let i = match cur.find(k) {
+                Ok(i) => i,
+                Err(i) => {
+                    cur.children.insert(
+                        i,
+                        Node {
+                            label: rest.to_vec(),
+                            first: Some(k.clone()),
+                            ..Default::default()
+                        },
+                    );
+                    rest = &[];
+                    cur = &mut cur.children[i];
+                    break;
+                }
+            };
+            let child = &mut cur.children[i];
+            let common = child
+                .label
+                .iter()
+                .zip(rest)
+                .take_while(|(a, b)| a == b)
+                .count();
+            // the key leaves the edge part way: split it there
+            if common < child.label.len() {
+                let label = child.label.split_off(common);
+                let lower = Node {
+                    first: Some(label[0].clone()),
+                    label,
+                    children: mem::take(&mut child.children),
+                    val: child.val.take(),
+                    count: mem::take(&mut child.count),
+                };
+                child.children.push(lower);
+            }
+            rest = &rest[common..];
+            cur = child;
+        }
+        debug_assert!(rest.is_empty());
+        cur.count += 1;
+        cur.val.replace(val)
+    }
+    /// The node `key` ends at, and for a key ending inside an edge, the
+    /// atoms of the edge left over.
+    fn node(&self, key: &[K]) -> Option<(&Node<K, V>, &[K])> {
+        let mut cur = &self.root;
+        let mut rest = key;
+        while let Some(k) = rest.first() {
+            let child = &cur.children[cur.find(k).ok()?];
+            if rest.len() < child.label.len() {
+                return child
+                    .label
+                    .starts_with(rest)
+                    .then(|| (child, &child.label[rest.len()..]));
+            }
+            rest = rest.strip_prefix(&child.label[..])?;
+            cur = child;
+        }
+        Some((cur, &[]))
+    }
+    pub fn exists(&self, key: impl Key<K>) -> Option<&Node<K, V>> {
+        let mut atoms = key.atoms();
+        let mut cur = &self.root;
+        while let Some(k) = atoms.next() {
+            let child = &cur.children[cur.find(&k).ok()?];
+            // the first atom matched when the child was found
+            for l in &child.label[1..] {
+                if atoms.next().as_ref() != Some(l) {
+                    return None;
+                }
+            }
+            cur = child;
+        }
+        (cur.count > 0).then_some(cur)
+    }
+    pub fn get(&self, key: impl Key<K>) -> Option<&V> {
+        self.exists(key)?.val.as_ref()
+    }
+    /// The keys starting with `prefix`, least inserted first, like
+    /// [`Trie::search`](crate::Trie::search).
+    pub fn search<Q>(&self, prefix: impl Key<K>) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        fn collect<K: Clone, V>(n: &Node<K, V>, path: &mut Vec<K>, out: &mut Vec<(usize, Vec<K>)>) {
+            if n.count > 0 {
+                out.push((n.count, path.clone()));
+            }
+            for child in &n.children {
+                path.extend_from_slice(&child.label);
+                collect(child, path, out);
+                path.truncate(path.len() - child.label.len());
+            }
+        }
+        let mut path: Vec<K> = prefix.atoms().collect();
+        let Some((start, rest)) = self.node(&path) else {
+            return Vec::new();
+        };
+        path.extend_from_slice(rest);
+        let mut results = Vec::new();
+        collect(start, &mut path, &mut results);
+        results.sort();
+        results
+            .into_iter()
+            .map(|(_, key)| key.into_iter().collect())
+            .collect()
+    }
+    /// Nodes, the root included.
+    pub fn nodes(&self) -> usize {
+        fn nodes<K, V>(n: &Node<K, V>) -> usize {
+            1 + n.children.iter().map(nodes).sum::<usize>()
+        }
+        nodes(&self.root)
+    }
+    /// Bytes taken by the nodes and their labels, not counting what the
+    /// values own.
+    pub fn memory(&self) -> usize {
+        fn memory<K, V>(n: &Node<K, V>) -> usize {
+            n.label.capacity() * mem::size_of::<K>()
+                + n.children.capacity() * mem::size_of::<Node<K, V>>()
+                + n.children.iter().map(memory).sum::<usize>()
+        }
+        mem::size_of::<Self>() + memory(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bench, Trie};
+
+    #[test]
+    fn splits_edges() {
+        let mut radix: RadixTrie<char, usize> = RadixTrie::new();
+        radix.insert("test", 0);
+        assert_eq!(radix.nodes(), 2);
+        // "team" leaves the edge after "te", which splits there
+        radix.insert("team", 1);
+        assert_eq!(radix.nodes(), 4);
+        let te = &radix.root.children[0];
+        assert_eq!(te.label, ['t', 'e']);
+        assert_eq!(te.count, 0);
+        let labels: Vec<_> = te.children.iter().map(|n| n.label.clone()).collect();
+        assert_eq!(labels, [vec!['a', 'm'], vec!['s', 't']]);
+        assert!(radix.exists("te").is_none());
+
+        // a key ending at the split point takes the node made there
+        radix.insert("te", 2);
+        assert_eq!(radix.nodes(), 4);
+        assert_eq!(radix.get("te"), Some(&2));
+        assert_eq!(radix.get("test"), Some(&0));
+        assert_eq!(radix.get("team"), Some(&1));
+
+        // as does one ending part way along a single edge
+        let mut radix: RadixTrie<char, usize> = RadixTrie::new();
+        radix.insert("test", 0);
+        radix.insert("te", 1);
+        assert_eq!(radix.nodes(), 3);
+        assert_eq!(radix.root.children[0].label, ['t', 'e']);
+        assert_eq!(radix.root.children[0].children[0].label, ['s', 't']);
+        assert_eq!(radix.get("te"), Some(&1));
+        assert_eq!(radix.get("test"), Some(&0));
+    }
+
+    #[test]
+    fn stops_inside_edges() {
+        let mut radix: RadixTrie<char, ()> = RadixTrie::new();
+        radix.insert("test", ());
+        radix.insert("team", ());
+        for inside in ["t", "tes", "tea"] {
+            assert!(radix.exists(inside).is_none(), "{inside}");
+            assert!(radix.get(inside).is_none());
+        }
+        assert!(radix.exists("teams").is_none());
+        assert!(radix.exists("tesT").is_none());
+        assert_eq!(radix.search::<String>("tes"), ["test"]);
+        assert_eq!(radix.search::<String>("t"), ["team", "test"]);
+        assert!(radix.search::<String>("tex").is_empty());
+    }
+
+    #[test]
+    fn matches_trie() {
+        let mut radix: RadixTrie<char, usize> = RadixTrie::new();
+        for (i, w) in [
+            "providences",
+            "province",
+            "prof",
+            "provinces",
+            "pro",
+            "",
+            "prof",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            radix.insert(w, i);
+        }
+        // root, pro, f, vi, dences, nce, s: one node per branching point
+        assert_eq!(radix.nodes(), 7);
+        assert_eq!(radix.root.children[0].label, ['p', 'r', 'o']);
+        assert_eq!(radix.get("prof"), Some(&6));
+        assert_eq!(radix.exists("prof").map(|n| n.count), Some(2));
+        assert_eq!(radix.get(""), Some(&5));
+        assert!(radix.exists("provin").is_none());
+        assert!(radix.exists("provincess").is_none());
+        assert_eq!(radix.search::<String>("provin"), ["province", "provinces"]);
+        assert_eq!(radix.search::<String>("provinces"), ["provinces"]);
+        assert!(radix.search::<String>("provinx").is_empty());
+
+        let words = bench::words(2000, 7);
+        let mut trie: Trie<char, ()> = Trie::new();
+        let mut radix: RadixTrie<char, ()> = RadixTrie::new();
+        for w in words.iter().chain(words.iter().step_by(3)) {
+            trie.insert(w.as_str(), ());
+            radix.insert(w.as_str(), ());
+        }
+        for w in &words {
+            for query in [w.as_str(), &w[..w.len() / 2], &w[..1]] {
+                assert_eq!(
+                    radix.exists(query).map(|n| n.count),
+                    trie.exists(query).map(|n| n.count),
+                    "{query}"
+                );
+                assert_eq!(
+                    radix.search::<String>(query),
+                    trie.search::<String>(query),
+                    "{query}"
+                );
+            }
+        }
+        assert!(radix.nodes() < trie.nodes());
+        assert!(radix.memory() < trie.memory());
+    }
+}
@@ -0,0 +1,96 @@
+//! A [`Trie`] shared between threads. Readers take the current version
+//! without locking, so lookups never wait on a writer; a writer changes a
+//! copy and publishes it whole, so readers see each write completely or
+//! not at all.
+//!
+//! Every write copies the whole trie, so one insert or remove costs
+//! `O(size of the trie)`, not the `O(key length)` of a write that locks or
+//! copies only the nodes on its key's path. That suits a dictionary read
+//! all the time and updated now and then, but not a stream of small
+//! writes: batch those with [`SharedTrie::update`], which pays for one
+//! copy however many changes it makes.
+
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+
+use crate::{Key, Trie};
+
+#[derive(Debug)]
+pub struct SharedTrie<K, V> {
+    current: ArcSwap<Trie<K, V>>,
+    /// held while a copy is changed, so two writes cannot lose one another
+    writer: Mutex<()>,
+}
+
+impl<K, V> Default for SharedTrie<K, V> {
+    fn default() -> Self {
+        SharedTrie::from(Trie::default())
+    }
+}
+
+impl<K, V> From<Trie<K, V>> for SharedTrie<K, V> {
+    fn from(trie: Trie<K, V>) -> Self {
+        SharedTrie {
+            current: ArcSwap::from_pointee(trie),
+            writer: Mutex::new(()),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> SharedTrie<K, V> {
+    pub fn new() -> Self {
+        SharedTrie {
+            ..Default::default()
+        }
+    }
+    /// The current version, which later writes leave as it is: queries
+    /// on it agree with each other.
+    pub fn snapshot(&self) -> Arc<Trie<K, V>> {
+        self.current.load_full()
+    }
+    /// Applies `f` to a copy of the trie and publishes it, returning what
+    /// `f` does. Readers see all of its changes at once.
+    pub fn update<R>(&self, f: impl FnOnce(&mut Trie<K, V>) -> R) -> R {
+        let _writing = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let mut trie = Trie::clone(&self.current.load());
+        let r = f(&mut trie);
+        self.current.store(Arc::new(trie));
+        r
+    }
+    /// Stores `val` under `key`, returning the value it replaces.
+    ///
+    /// This copies the whole trie, so loading `n` keys one insert at a
+    /// time takes `O(n²)`: insert them in one [`update`](Self::update), or
+    /// build a [`Trie`] and convert it.
+    pub fn insert(&self, key: impl Key<K>, val: V) -> Option<V> {
+        self.update(|t| t.insert(key, val))
+    }
+    /// Removes one insertion of `key`, like [`Trie::remove`]. This copies
+    /// the whole trie too; remove many keys in one
+    /// [`update`](Self::update).
+    pub fn remove(&self, key: impl Key<K>) -> bool {
+        self.update(|t| t.remove(key))
+    }
+    /// How many times `key` was inserted, if it was.
+    pub fn exists(&self, key: impl Key<K>) -> Option<usize> {
+        self.current.load().exists(key).map(|n| n.count)
+    }
+    pub fn get(&self, key: impl Key<K>) -> Option<V> {
+        self.current.load().get(key).cloned()
+    }
+    /// Like [`Trie::search`], on the current version.
+    pub fn search<Q>(&self, prefix: impl Key<K>) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        self.current.load().search(prefix)
+    }
+    /// Like [`Trie::complete`], on the current version.
+    pub fn complete<Q>(&self, prefix: impl Key<K>, k: usize) -> Vec<Q>
+    where
+        Q: FromIterator<K>,
+    {
+        self.current.load().complete(prefix, k)
+    }
+}